@@ -1,4 +1,49 @@
 <?xml version="1.0" encoding="UTF-8"?>
 <tileset version="1.10" tiledversion="1.11.2" name="Plants" tilewidth="32" tileheight="32" tilecount="256" columns="16">
  <image source="Plants.png" width="512" height="512"/>
+ <tile id="66">
+  <objectgroup draworder="index" id="2">
+   <object id="1" x="9" y="6" width="14" height="18"/>
+  </objectgroup>
+ </tile>
+ <tile id="70">
+  <objectgroup draworder="index" id="2">
+   <object id="1" x="9" y="6" width="14" height="18"/>
+  </objectgroup>
+ </tile>
+ <tile id="74">
+  <objectgroup draworder="index" id="2">
+   <object id="1" x="9" y="6" width="14" height="18"/>
+  </objectgroup>
+ </tile>
+ <tile id="97">
+  <objectgroup draworder="index" id="2">
+   <object id="1" x="4" y="6" width="24" height="20"/>
+  </objectgroup>
+ </tile>
+ <tile id="99">
+  <objectgroup draworder="index" id="2">
+   <object id="1" x="4" y="6" width="24" height="20"/>
+  </objectgroup>
+ </tile>
+ <tile id="101">
+  <objectgroup draworder="index" id="2">
+   <object id="1" x="4" y="6" width="24" height="20"/>
+  </objectgroup>
+ </tile>
+ <tile id="103">
+  <objectgroup draworder="index" id="2">
+   <object id="1" x="4" y="6" width="24" height="20"/>
+  </objectgroup>
+ </tile>
+ <tile id="105">
+  <objectgroup draworder="index" id="2">
+   <object id="1" x="4" y="6" width="24" height="20"/>
+  </objectgroup>
+ </tile>
+ <tile id="107">
+  <objectgroup draworder="index" id="2">
+   <object id="1" x="4" y="6" width="24" height="20"/>
+  </objectgroup>
+ </tile>
 </tileset>
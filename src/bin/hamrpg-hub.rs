@@ -0,0 +1,213 @@
+// HamRPG hub - a virtual shared frequency for running several clients on one machine
+// Listens for KISS over TCP connections (the same protocol direwolf offers) and
+// rebroadcasts every data frame to all other connected clients.
+//
+// Usage: hamrpg-hub [--bind ADDR] [--port PORT] [--bit-rate BPS] [--txdelay MS] [--verbose]
+//
+// With --bit-rate the hub plays frames out one at a time, holding each for the
+// airtime it would take on a real modem, so clients see realistic latency.
+// --verbose logs every frame heard, which is a lot on a busy hub.
+#[path = "../connection/airtime.rs"]
+mod airtime;
+#[path = "../connection/kiss.rs"]
+mod kiss;
+
+use airtime::frame_airtime;
+use crossbeam_channel::{Receiver, Sender, TrySendError, bounded, unbounded};
+use kiss::{KissDecoder, data_payload, encode_frame};
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// A client that can't take a frame for this long is dropped
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+// Frames waiting for a slow client; more than this and it misses frames
+const CLIENT_BACKLOG: usize = 64;
+
+struct HubConfig {
+    bind: String,
+    port: u16,
+    bit_rate: Option<u32>,
+    txdelay_ms: u32,
+    // Log every frame heard
+    verbose: bool,
+}
+
+// Frame heard from one client, to be repeated to the others
+struct HubFrame {
+    from: usize,
+    payload: Vec<u8>,
+}
+
+// Each client's writer thread takes encoded frames from its own channel, so a
+// stalled client never holds up the others
+type Clients = Arc<Mutex<Vec<(usize, Sender<Arc<Vec<u8>>>)>>>;
+
+fn main() {
+    let config = match parse_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("[!] {}", e);
+            println!("Usage: hamrpg-hub [--bind ADDR] [--port PORT] [--bit-rate BPS] [--txdelay MS] [--verbose]");
+            std::process::exit(2);
+        }
+    };
+
+    let listener = match TcpListener::bind((config.bind.as_str(), config.port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            println!("[!] Failed to listen on {}:{}: {}", config.bind, config.port, e);
+            std::process::exit(1);
+        }
+    };
+    println!("[i] HamRPG hub listening on {}:{}", config.bind, config.port);
+    match config.bit_rate {
+        Some(bit_rate) => println!(
+            "[i] Airtime throttling: {} bps, TXDELAY {} ms",
+            bit_rate, config.txdelay_ms
+        ),
+        None => println!("[i] Airtime throttling disabled"),
+    }
+
+    let clients: Clients = Arc::new(Mutex::new(Vec::new()));
+    let (tx_frames, rx_frames) = unbounded::<HubFrame>();
+
+    // Repeat frames to every client except the one that sent them
+    let broadcast_clients = Arc::clone(&clients);
+    thread::spawn(move || {
+        for frame in rx_frames {
+            if let Some(bit_rate) = config.bit_rate {
+                thread::sleep(frame_airtime(frame.payload.len(), bit_rate, config.txdelay_ms));
+            }
+            let encoded = Arc::new(encode_frame(&frame.payload));
+            let clients = broadcast_clients.lock().unwrap();
+            for (id, writer) in clients.iter() {
+                if *id == frame.from {
+                    continue;
+                }
+                if let Err(TrySendError::Full(_)) = writer.try_send(Arc::clone(&encoded)) {
+                    println!("[!] Client {} is falling behind, frame not repeated to it", id);
+                }
+            }
+        }
+    });
+
+    for (id, stream) in listener.incoming().enumerate() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                println!("[!] Failed to accept connection: {}", e);
+                continue;
+            }
+        };
+        let peer = stream
+            .peer_addr()
+            .map(|a| a.to_string())
+            .unwrap_or_else(|_| "unknown".to_string());
+        let writer = match stream
+            .set_write_timeout(Some(WRITE_TIMEOUT))
+            .and_then(|_| stream.try_clone())
+        {
+            Ok(writer) => writer,
+            Err(e) => {
+                println!("[!] Failed to set up client {}: {}", peer, e);
+                continue;
+            }
+        };
+        println!("[i] Client {} connected from {}", id, peer);
+        let (tx_client, rx_client) = bounded(CLIENT_BACKLOG);
+        clients.lock().unwrap().push((id, tx_client));
+        thread::spawn(move || write_client(id, writer, rx_client));
+
+        let tx_frames = tx_frames.clone();
+        let clients = Arc::clone(&clients);
+        let verbose = config.verbose;
+        thread::spawn(move || {
+            read_client(id, stream, tx_frames, verbose);
+            clients.lock().unwrap().retain(|(client, _)| *client != id);
+            println!("[i] Client {} disconnected", id);
+        });
+    }
+}
+
+// Send repeated frames to one client until it disconnects or stalls, then close
+// the connection so its reader stops too
+fn write_client(id: usize, mut stream: TcpStream, frames: Receiver<Arc<Vec<u8>>>) {
+    for frame in frames {
+        if let Err(e) = stream.write_all(&frame) {
+            println!("[!] Dropping client {}: {}", id, e);
+            let _ = stream.shutdown(Shutdown::Both);
+            return;
+        }
+    }
+}
+
+// Split a client's KISS stream into frames and queue the data frames
+fn read_client(id: usize, mut stream: TcpStream, tx_frames: Sender<HubFrame>, verbose: bool) {
+    let mut decoder = KissDecoder::default();
+    let mut buf = [0u8; 1024];
+    loop {
+        let n = match stream.read(&mut buf) {
+            Ok(0) | Err(_) => return,
+            Ok(n) => n,
+        };
+        for &byte in &buf[..n] {
+            let Some(frame) = decoder.push(byte) else {
+                continue;
+            };
+            // KISS control frames (TXDELAY, persistence, ...) mean nothing on a virtual channel
+            let Some(payload) = data_payload(&frame) else {
+                continue;
+            };
+            if verbose {
+                println!("[i] Client {}: {} byte frame", id, payload.len());
+            }
+            if tx_frames
+                .send(HubFrame {
+                    from: id,
+                    payload: payload.to_vec(),
+                })
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<HubConfig, String> {
+    let mut config = HubConfig {
+        bind: "127.0.0.1".to_string(),
+        port: 8100,
+        bit_rate: None,
+        txdelay_ms: 300,
+        verbose: false,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--bind" => config.bind = value()?,
+            "--port" => {
+                config.port = value()?.parse().map_err(|_| "Invalid port".to_string())?
+            }
+            "--bit-rate" => {
+                config.bit_rate = Some(
+                    value()?
+                        .parse()
+                        .ok()
+                        .filter(|&bit_rate| bit_rate > 0)
+                        .ok_or_else(|| "Invalid bit rate".to_string())?,
+                )
+            }
+            "--txdelay" => {
+                config.txdelay_ms = value()?.parse().map_err(|_| "Invalid TXDELAY".to_string())?
+            }
+            "--verbose" => config.verbose = true,
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+    Ok(config)
+}
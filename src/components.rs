@@ -22,6 +22,17 @@ pub struct RemotePlayer {
     pub move_start_time: Instant,
     pub move_duration: f32, // Time to complete the movement
     pub is_moving: bool,
+    // Facing and walking/idle state reported in the last position packet
+    pub facing: String,
+    pub reported_moving: bool,
+    // Velocity hint from the last fix, used to extrapolate between fixes
+    pub velocity: Vec2,
+    // Seconds to extrapolate along `velocity` before stopping (horizon or a wall)
+    pub extrapolation_time: f32,
+    // Waypoints around obstacles from start_position to target_position, if any
+    pub path: Vec<Vec3>,
+    // Set while fading out and back in at the target when no path exists
+    pub fade_start: Option<Instant>,
 }
 // Collection of 8-directional animation ranges for player sprites
 #[derive(Component)]
@@ -35,6 +46,35 @@ pub struct Animations {
     pub idle_north: AnimationIndices,
     pub idle_south: AnimationIndices,
 }
+
+impl Animations {
+    // Facing direction ("north", "east", "south" or "west") of the current animation
+    pub fn facing(&self, current: &AnimationIndices) -> &'static str {
+        if *current == self.walk_north || *current == self.idle_north {
+            "north"
+        } else if *current == self.walk_east || *current == self.idle_east {
+            "east"
+        } else if *current == self.walk_west || *current == self.idle_west {
+            "west"
+        } else {
+            "south"
+        }
+    }
+
+    // Walking or idle animation for a facing direction
+    pub fn for_state(&self, facing: &str, moving: bool) -> AnimationIndices {
+        match (facing, moving) {
+            ("north", true) => self.walk_north,
+            ("north", false) => self.idle_north,
+            ("east", true) => self.walk_east,
+            ("east", false) => self.idle_east,
+            ("west", true) => self.walk_west,
+            ("west", false) => self.idle_west,
+            (_, true) => self.walk_south,
+            (_, false) => self.idle_south,
+        }
+    }
+}
 // Timer for controlling animation frame rate
 #[derive(Component, Deref, DerefMut)]
 pub struct AnimationTimer(pub Timer);
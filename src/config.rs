@@ -0,0 +1,488 @@
+// Station profiles kept in game_config.ini
+//
+// Each profile is a section holding every setting of the connection menu, and
+// [Game] names the one to start with:
+//
+// [Game]
+// active_profile = Home HF via VARA
+//
+// [Station Home HF via VARA]
+// callsign = N0CALL-1
+// link_type = tcp_kiss
+// tnc_host = 127.0.0.1
+// tnc_port = 8300
+// position_update_time = 120
+// listen_only = false
+// profile = FT-891, 40m dipole
+// bit_rate = 1200
+// txdelay_ms = 300
+// max_duty_cycle = 10
+// paclen = 128
+//
+// Files from older versions, with the settings directly under [Game], load as a
+// single profile named "Default". Missing or malformed values fall back to the
+// defaults, and a file that can't be parsed at all is kept as a .bak when the
+// configuration is next saved.
+//
+// Game settings that don't depend on the station (audio, keys, display) are in
+// [Settings].
+
+use crate::menu::{LinkType, MenuConfig, PACLEN_RANGE};
+use bevy::prelude::*;
+use ini::{Ini, Properties};
+
+const CONFIG_FILE: &str = "game_config.ini";
+const GAME_SECTION: &str = "Game";
+const STATION_PREFIX: &str = "Station ";
+const SETTINGS_SECTION: &str = "Settings";
+pub const DEFAULT_PROFILE: &str = "Default";
+
+// Every menu setting that is kept between sessions
+#[derive(Clone)]
+pub struct StationProfile {
+    pub callsign: String,
+    pub link_type: LinkType,
+    pub tnc_host: String,
+    pub tnc_port: String,
+    pub position_update_time: String,
+    pub listen_only: bool,
+    pub profile: String,
+    pub bit_rate: String,
+    pub txdelay_ms: String,
+    pub max_duty_cycle: String,
+    pub paclen: String,
+}
+
+impl StationProfile {
+    pub fn from_menu(menu_config: &MenuConfig) -> Self {
+        Self {
+            callsign: menu_config.callsign.clone(),
+            link_type: menu_config.link_type,
+            tnc_host: menu_config.tnc_host.clone(),
+            tnc_port: menu_config.tnc_port.clone(),
+            position_update_time: menu_config.position_update_time.clone(),
+            listen_only: menu_config.listen_only,
+            profile: menu_config.profile.clone(),
+            bit_rate: menu_config.bit_rate.clone(),
+            txdelay_ms: menu_config.txdelay_ms.clone(),
+            max_duty_cycle: menu_config.max_duty_cycle.clone(),
+            paclen: menu_config.paclen.clone(),
+        }
+    }
+
+    pub fn apply_to(&self, menu_config: &mut MenuConfig) {
+        menu_config.callsign = self.callsign.clone();
+        menu_config.link_type = self.link_type;
+        menu_config.tnc_host = self.tnc_host.clone();
+        menu_config.tnc_port = self.tnc_port.clone();
+        menu_config.position_update_time = self.position_update_time.clone();
+        menu_config.listen_only = self.listen_only;
+        menu_config.profile = self.profile.clone();
+        menu_config.bit_rate = self.bit_rate.clone();
+        menu_config.txdelay_ms = self.txdelay_ms.clone();
+        menu_config.max_duty_cycle = self.max_duty_cycle.clone();
+        menu_config.paclen = self.paclen.clone();
+    }
+
+    // Read a section, keeping the default for anything missing or unusable
+    fn from_section(name: &str, section: &Properties) -> Self {
+        let mut station = Self::from_menu(&MenuConfig::new());
+        let text = |key: &str| section.get(key).map(str::trim).filter(|value| !value.is_empty());
+
+        if let Some(callsign) = text("callsign") {
+            station.callsign = callsign.to_string();
+        }
+        if let Some(link_type) = text("link_type") {
+            match parse_link_type(link_type) {
+                Some(link_type) => station.link_type = link_type,
+                None => println!("[!] {}: unknown link_type '{}'", name, link_type),
+            }
+        }
+        if let Some(host) = text("tnc_host") {
+            station.tnc_host = host.to_string();
+        }
+        if let Some(port) = text("tnc_port") {
+            station.tnc_port = port.to_string();
+        }
+        if let Some(interval) = text("position_update_time") {
+            match interval.parse::<u64>() {
+                Ok(seconds) if seconds > 0 => station.position_update_time = seconds.to_string(),
+                _ => println!("[!] {}: bad position_update_time '{}'", name, interval),
+            }
+        }
+        if let Some(listen_only) = text("listen_only") {
+            match parse_bool(listen_only) {
+                Some(listen_only) => station.listen_only = listen_only,
+                None => println!("[!] {}: bad listen_only '{}'", name, listen_only),
+            }
+        }
+        if let Some(bit_rate) = text("bit_rate") {
+            match bit_rate.parse::<u32>() {
+                Ok(bps) if bps > 0 => station.bit_rate = bps.to_string(),
+                _ => println!("[!] {}: bad bit_rate '{}'", name, bit_rate),
+            }
+        }
+        if let Some(txdelay) = text("txdelay_ms") {
+            match txdelay.parse::<u32>() {
+                Ok(ms) => station.txdelay_ms = ms.to_string(),
+                _ => println!("[!] {}: bad txdelay_ms '{}'", name, txdelay),
+            }
+        }
+        if let Some(duty_cycle) = text("max_duty_cycle") {
+            match duty_cycle.parse::<f32>() {
+                Ok(percent) if percent > 0.0 && percent <= 100.0 => {
+                    station.max_duty_cycle = duty_cycle.to_string()
+                }
+                _ => println!("[!] {}: bad max_duty_cycle '{}'", name, duty_cycle),
+            }
+        }
+        if let Some(paclen) = text("paclen") {
+            match paclen.parse::<usize>() {
+                Ok(bytes) if PACLEN_RANGE.contains(&bytes) => station.paclen = bytes.to_string(),
+                _ => println!("[!] {}: bad paclen '{}'", name, paclen),
+            }
+        }
+        // An empty profile line is a valid choice
+        if let Some(profile) = section.get("profile") {
+            station.profile = profile.trim().to_string();
+        }
+        station
+    }
+
+    fn write_to(&self, conf: &mut Ini, section: &str) {
+        conf.with_section(Some(section))
+            .set("callsign", self.callsign.as_str())
+            .set("link_type", link_type_name(self.link_type))
+            .set("tnc_host", self.tnc_host.as_str())
+            .set("tnc_port", self.tnc_port.as_str())
+            .set("position_update_time", self.position_update_time.as_str())
+            .set("listen_only", if self.listen_only { "true" } else { "false" })
+            .set("profile", self.profile.as_str())
+            .set("bit_rate", self.bit_rate.as_str())
+            .set("txdelay_ms", self.txdelay_ms.as_str())
+            .set("max_duty_cycle", self.max_duty_cycle.as_str())
+            .set("paclen", self.paclen.as_str());
+    }
+}
+
+fn link_type_name(link_type: LinkType) -> &'static str {
+    match link_type {
+        LinkType::TcpKiss => "tcp_kiss",
+        LinkType::SerialKiss => "serial_kiss",
+        LinkType::Simulated => "simulated",
+    }
+}
+
+fn parse_link_type(name: &str) -> Option<LinkType> {
+    match name.to_ascii_lowercase().as_str() {
+        "tcp_kiss" | "tcp" => Some(LinkType::TcpKiss),
+        "serial_kiss" | "serial" => Some(LinkType::SerialKiss),
+        "simulated" | "sim" => Some(LinkType::Simulated),
+        _ => None,
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+// All station profiles, in file order, and the one in use
+#[derive(Resource)]
+pub struct GameConfig {
+    pub active: String,
+    pub stations: Vec<(String, StationProfile)>,
+    // The file on disk couldn't be parsed, so keep it aside before overwriting it
+    malformed: bool,
+}
+
+impl GameConfig {
+    // Never fails: a missing or broken file gives a single default profile
+    pub fn load() -> Self {
+        let mut config = Self {
+            active: DEFAULT_PROFILE.to_string(),
+            stations: Vec::new(),
+            malformed: false,
+        };
+
+        match Ini::load_from_file(CONFIG_FILE) {
+            Ok(conf) => {
+                for (section, properties) in conf.iter() {
+                    let Some(name) = section.and_then(|s| s.strip_prefix(STATION_PREFIX)) else {
+                        continue;
+                    };
+                    let name = name.trim();
+                    if !name.is_empty() && config.get(name).is_none() {
+                        let station = StationProfile::from_section(name, properties);
+                        config.stations.push((name.to_string(), station));
+                    }
+                }
+                if let Some(game) = conf.section(Some(GAME_SECTION)) {
+                    if let Some(active) = game.get("active_profile").map(str::trim) {
+                        config.active = active.to_string();
+                    } else if config.stations.is_empty() {
+                        // Settings from before there were profiles
+                        let station = StationProfile::from_section(GAME_SECTION, game);
+                        config.stations.push((DEFAULT_PROFILE.to_string(), station));
+                    }
+                }
+            }
+            Err(ini::Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                println!("[!] {} is malformed ({}) - using defaults", CONFIG_FILE, e);
+                config.malformed = true;
+            }
+        }
+
+        if config.stations.is_empty() {
+            config.stations.push((
+                DEFAULT_PROFILE.to_string(),
+                StationProfile::from_menu(&MenuConfig::new()),
+            ));
+        }
+        if config.get(&config.active).is_none() {
+            println!("[!] No station profile named '{}'", config.active);
+            config.active = config.stations[0].0.clone();
+        }
+        config
+    }
+
+    // Rewrite [Game] and the station sections, leaving any others as they are
+    pub fn save(&mut self) -> Result<(), String> {
+        let mut conf = if self.malformed {
+            let backup = format!("{}.bak", CONFIG_FILE);
+            std::fs::rename(CONFIG_FILE, &backup)
+                .map_err(|e| format!("Failed to keep {} as {}: {}", CONFIG_FILE, backup, e))?;
+            println!("[i] Kept the malformed {} as {}", CONFIG_FILE, backup);
+            self.malformed = false;
+            Ini::new()
+        } else {
+            Ini::load_from_file(CONFIG_FILE).unwrap_or_default()
+        };
+
+        let ours: Vec<String> = conf
+            .sections()
+            .flatten()
+            .filter(|section| *section == GAME_SECTION || section.starts_with(STATION_PREFIX))
+            .map(str::to_string)
+            .collect();
+        for section in ours {
+            while conf.delete(Some(section.as_str())).is_some() {}
+        }
+        conf.with_section(Some(GAME_SECTION))
+            .set("active_profile", self.active.as_str());
+        for (name, station) in &self.stations {
+            station.write_to(&mut conf, &format!("{}{}", STATION_PREFIX, name));
+        }
+        conf.write_to_file(CONFIG_FILE)
+            .map_err(|e| format!("Failed to write {}: {}", CONFIG_FILE, e))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&StationProfile> {
+        self.stations
+            .iter()
+            .find(|(station_name, _)| station_name == name)
+            .map(|(_, station)| station)
+    }
+
+    pub fn active_station(&self) -> &StationProfile {
+        // load() guarantees the active profile exists
+        self.get(&self.active).unwrap_or(&self.stations[0].1)
+    }
+
+    // Add or replace a profile and make it the active one
+    pub fn set(&mut self, name: &str, station: StationProfile) {
+        match self.stations.iter_mut().find(|(station_name, _)| station_name == name) {
+            Some((_, existing)) => *existing = station,
+            None => self.stations.push((name.to_string(), station)),
+        }
+        self.active = name.to_string();
+    }
+
+    // Remove a profile; the last one can't be removed
+    pub fn remove(&mut self, name: &str) {
+        if self.stations.len() <= 1 {
+            return;
+        }
+        self.stations.retain(|(station_name, _)| station_name != name);
+        if self.active == name {
+            self.active = self.stations[0].0.clone();
+        }
+    }
+}
+
+// Keys for everything the player can do
+#[derive(Clone, Copy, PartialEq)]
+pub struct KeyBindings {
+    pub up: KeyCode,
+    pub down: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub chat: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            up: KeyCode::KeyW,
+            down: KeyCode::KeyS,
+            left: KeyCode::KeyA,
+            right: KeyCode::KeyD,
+            chat: KeyCode::KeyT,
+        }
+    }
+}
+
+// Keys that can be bound to an action. Escape is kept for the settings window.
+pub const BINDABLE_KEYS: &[KeyCode] = &[
+    KeyCode::KeyA, KeyCode::KeyB, KeyCode::KeyC, KeyCode::KeyD, KeyCode::KeyE, KeyCode::KeyF,
+    KeyCode::KeyG, KeyCode::KeyH, KeyCode::KeyI, KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL,
+    KeyCode::KeyM, KeyCode::KeyN, KeyCode::KeyO, KeyCode::KeyP, KeyCode::KeyQ, KeyCode::KeyR,
+    KeyCode::KeyS, KeyCode::KeyT, KeyCode::KeyU, KeyCode::KeyV, KeyCode::KeyW, KeyCode::KeyX,
+    KeyCode::KeyY, KeyCode::KeyZ, KeyCode::Digit0, KeyCode::Digit1, KeyCode::Digit2,
+    KeyCode::Digit3, KeyCode::Digit4, KeyCode::Digit5, KeyCode::Digit6, KeyCode::Digit7,
+    KeyCode::Digit8, KeyCode::Digit9, KeyCode::ArrowUp, KeyCode::ArrowDown, KeyCode::ArrowLeft,
+    KeyCode::ArrowRight, KeyCode::Space, KeyCode::Enter, KeyCode::Tab, KeyCode::ShiftLeft,
+    KeyCode::ShiftRight, KeyCode::ControlLeft, KeyCode::ControlRight,
+];
+
+// Keys are stored by their KeyCode name, e.g. KeyW or ArrowUp
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS
+        .iter()
+        .copied()
+        .find(|&key| key_name(key).eq_ignore_ascii_case(name))
+}
+
+// Settings changed from the in-game settings window
+#[derive(Resource, Clone, PartialEq)]
+pub struct Settings {
+    // Music volume, 0 to 1
+    pub volume: f32,
+    // Screen pixels per world pixel
+    pub zoom: f32,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    // Beacon positions in a time slot instead of at a jittered interval
+    pub slotted_beacons: bool,
+    // Ask stations to acknowledge our chat, and resend it until one does
+    pub ack_chat: bool,
+    pub keys: KeyBindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            volume: 0.1,
+            zoom: 4.0,
+            fullscreen: false,
+            vsync: true,
+            slotted_beacons: false,
+            ack_chat: false,
+            keys: KeyBindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub const VOLUME_RANGE: std::ops::RangeInclusive<f32> = 0.0..=1.0;
+    pub const ZOOM_RANGE: std::ops::RangeInclusive<f32> = 1.0..=8.0;
+
+    // Read [Settings], keeping the default for anything missing or unusable
+    pub fn load() -> Self {
+        let mut settings = Self::default();
+        let Ok(conf) = Ini::load_from_file(CONFIG_FILE) else {
+            return settings;
+        };
+        let Some(section) = conf.section(Some(SETTINGS_SECTION)) else {
+            return settings;
+        };
+        let text = |key: &str| section.get(key).map(str::trim).filter(|value| !value.is_empty());
+        let number = |key: &str, range: std::ops::RangeInclusive<f32>| {
+            let value = text(key)?;
+            match value.parse::<f32>() {
+                Ok(number) if number.is_finite() => Some(number.clamp(*range.start(), *range.end())),
+                _ => {
+                    println!("[!] {}: bad {} '{}'", SETTINGS_SECTION, key, value);
+                    None
+                }
+            }
+        };
+        let flag = |key: &str| {
+            let value = text(key)?;
+            let flag = parse_bool(value);
+            if flag.is_none() {
+                println!("[!] {}: bad {} '{}'", SETTINGS_SECTION, key, value);
+            }
+            flag
+        };
+
+        if let Some(volume) = number("volume", Self::VOLUME_RANGE) {
+            settings.volume = volume;
+        }
+        if let Some(zoom) = number("zoom", Self::ZOOM_RANGE) {
+            settings.zoom = zoom;
+        }
+        if let Some(fullscreen) = flag("fullscreen") {
+            settings.fullscreen = fullscreen;
+        }
+        if let Some(vsync) = flag("vsync") {
+            settings.vsync = vsync;
+        }
+        if let Some(slotted_beacons) = flag("slotted_beacons") {
+            settings.slotted_beacons = slotted_beacons;
+        }
+        if let Some(ack_chat) = flag("ack_chat") {
+            settings.ack_chat = ack_chat;
+        }
+        let keys = &mut settings.keys;
+        for (name, key) in [
+            ("key_up", &mut keys.up),
+            ("key_down", &mut keys.down),
+            ("key_left", &mut keys.left),
+            ("key_right", &mut keys.right),
+            ("key_chat", &mut keys.chat),
+        ] {
+            let Some(value) = text(name) else {
+                continue;
+            };
+            match parse_key(value) {
+                Some(parsed) => *key = parsed,
+                None => println!("[!] {}: unknown key '{}' for {}", SETTINGS_SECTION, value, name),
+            }
+        }
+        settings
+    }
+
+    // Rewrite [Settings], leaving the rest of the file as it is
+    pub fn save(&self) -> Result<(), String> {
+        let mut conf = match Ini::load_from_file(CONFIG_FILE) {
+            Ok(conf) => conf,
+            Err(ini::Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => Ini::new(),
+            Err(e) => {
+                return Err(format!("Not saving settings over malformed {}: {}", CONFIG_FILE, e));
+            }
+        };
+        conf.with_section(Some(SETTINGS_SECTION))
+            .set("volume", self.volume.to_string())
+            .set("zoom", self.zoom.to_string())
+            .set("fullscreen", self.fullscreen.to_string())
+            .set("vsync", self.vsync.to_string())
+            .set("slotted_beacons", self.slotted_beacons.to_string())
+            .set("ack_chat", self.ack_chat.to_string())
+            .set("key_up", key_name(self.keys.up))
+            .set("key_down", key_name(self.keys.down))
+            .set("key_left", key_name(self.keys.left))
+            .set("key_right", key_name(self.keys.right))
+            .set("key_chat", key_name(self.keys.chat));
+        conf.write_to_file(CONFIG_FILE)
+            .map_err(|e| format!("Failed to write {}: {}", CONFIG_FILE, e))
+    }
+}
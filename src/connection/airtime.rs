@@ -0,0 +1,14 @@
+// Airtime estimates for AX.25 frames
+// A frame on air is TXDELAY (keying up and flags) followed by the frame bits
+
+use std::time::Duration;
+
+// Opening/closing flags and the FCS, which never appear in Ax25Frame::to_bytes.
+// The address header, control and PID bytes are part of the frame length.
+const FRAMING_BYTES: usize = 4;
+
+// Time a frame of `frame_len` bytes occupies the channel
+pub fn frame_airtime(frame_len: usize, bit_rate: u32, txdelay_ms: u32) -> Duration {
+    let bits = ((frame_len + FRAMING_BYTES) * 8) as f64;
+    Duration::from_millis(txdelay_ms as u64) + Duration::from_secs_f64(bits / bit_rate.max(1) as f64)
+}
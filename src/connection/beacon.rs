@@ -0,0 +1,199 @@
+// Time-slotted position beacons
+//
+// With slotted beacons on, the position update interval is cut into slots of
+// SLOT_LENGTH seconds, counted from whole multiples of the interval since the
+// Unix epoch, so every station with a synced clock agrees where each slot starts.
+// A station beacons at the start of its slot, picked from a hash of its callsign.
+// Callsigns are hashed and sorted in their normalized form, as others decode
+// them off air, so every station agrees on each other's slots.
+//
+// Two stations in the same slot collide every time, and neither hears the
+// other's beacon, so a slot counts as taken both by the stations whose callsign
+// hashes to it and by the stations last heard beaconing in it. When our slot is
+// taken by a station whose callsign sorts first, we move to the next free one.
+// This only lines up between stations using the same interval.
+//
+// A frame can go out well after its slot starts (queued behind chat, held for
+// the duty cycle), so the slot a station beacons in is read from its position
+// packet, never from when the packet arrived.
+
+use super::callsign::normalize_callsign;
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Long enough for a position frame at 1200 bps, with room for clock error
+const SLOT_LENGTH: u64 = 2;
+// Stations not heard for this many intervals no longer hold a slot
+const OCCUPANCY_INTERVALS: u64 = 3;
+
+#[derive(Resource, Default)]
+pub struct BeaconSchedule {
+    // Interval the slots below were worked out for
+    interval: u64,
+    // Our slot, once known
+    slot: Option<u64>,
+    // Slot each station last beaconed in, if it sent one, and when we heard
+    // it (UTC seconds)
+    heard: HashMap<String, (Option<u64>, f64)>,
+    // Interval count since the epoch we last beaconed in
+    last_cycle: Option<u64>,
+}
+
+impl BeaconSchedule {
+    // Whether we are at the start of our slot and haven't beaconed in it yet
+    pub fn due(&mut self, callsign: &str, interval: u64) -> bool {
+        self.set_interval(interval);
+        let slot = self.own_slot(&normalize_callsign(callsign));
+        let now = utc_now();
+        let cycle = (now / self.interval as f64) as u64;
+        let start = (cycle * self.interval + slot * SLOT_LENGTH) as f64;
+        if self.last_cycle == Some(cycle) || now < start || now >= start + SLOT_LENGTH as f64 {
+            return false;
+        }
+        self.last_cycle = Some(cycle);
+        true
+    }
+
+    // Our slot as sent in position packets, once known. None if it doesn't fit.
+    pub fn slot(&self) -> Option<u8> {
+        self.slot.and_then(|slot| u8::try_from(slot).ok())
+    }
+
+    // Note a position from another station, with the slot it says it was sent
+    // in, and get out of its way if it holds our slot and its callsign sorts
+    // before ours
+    pub fn heard(&mut self, callsign: &str, slot: Option<u8>, own_callsign: &str, interval: u64) {
+        self.set_interval(interval);
+        let callsign = &normalize_callsign(callsign);
+        let own_callsign = &normalize_callsign(own_callsign);
+        let now = utc_now();
+        let slots = self.slots();
+        // Positions sent outside a slot, or sliced for another interval, only
+        // hold the station's home slot
+        let slot = slot.map(u64::from).filter(|&slot| slot < slots);
+        self.heard.insert(callsign.to_string(), (slot, now));
+        let forget = (OCCUPANCY_INTERVALS * self.interval) as f64;
+        self.heard.retain(|_, &mut (_, when)| now - when < forget);
+
+        let slot = self.own_slot(own_callsign);
+        if callsign >= own_callsign || !self.taken_by(callsign).contains(&slot) {
+            return;
+        }
+        let taken: HashSet<u64> = self
+            .heard
+            .keys()
+            .flat_map(|station| self.taken_by(station))
+            .collect();
+        match (1..slots).map(|step| (slot + step) % slots).find(|s| !taken.contains(s)) {
+            Some(free) => {
+                println!(
+                    "[i] {} beacons in our slot {}, moving to slot {} of {}",
+                    callsign, slot, free, slots
+                );
+                self.slot = Some(free);
+            }
+            None => println!("[!] {} beacons in our slot {}, but no slot is free", callsign, slot),
+        }
+    }
+
+    // Slots change size with the interval, so start over when it changes
+    fn set_interval(&mut self, interval: u64) {
+        let interval = interval.max(1);
+        if self.interval != interval {
+            self.interval = interval;
+            self.slot = None;
+            self.heard.clear();
+            self.last_cycle = None;
+        }
+    }
+
+    fn slots(&self) -> u64 {
+        (self.interval / SLOT_LENGTH).max(1)
+    }
+
+    fn own_slot(&mut self, callsign: &str) -> u64 {
+        let slots = self.slots();
+        *self.slot.get_or_insert_with(|| home_slot(callsign, slots))
+    }
+
+    // The slot a station's callsign hashes to, and the one it was last heard in
+    fn taken_by(&self, callsign: &str) -> [u64; 2] {
+        let home = home_slot(callsign, self.slots());
+        let heard = self.heard.get(callsign).and_then(|&(slot, _)| slot).unwrap_or(home);
+        [home, heard]
+    }
+}
+
+// FNV-1a, so every station works out the same slot for a callsign
+fn home_slot(callsign: &str, slots: u64) -> u64 {
+    let hash = callsign.bytes().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    });
+    hash as u64 % slots
+}
+
+fn utc_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: u64 = 30;
+    const SLOTS: u64 = INTERVAL / SLOT_LENGTH;
+
+    // A made-up callsign starting with `prefix` whose home slot is `slot`
+    fn station_in(prefix: &str, slot: u64) -> String {
+        (0..1000)
+            .map(|n| format!("{}{}", prefix, n))
+            .find(|callsign| home_slot(callsign, SLOTS) == slot)
+            .unwrap()
+    }
+
+    #[test]
+    fn earlier_callsign_in_our_slot_moves_us() {
+        let home = home_slot("N0CALL", SLOTS);
+        let other = station_in("A", home);
+        let mut schedule = BeaconSchedule::default();
+        schedule.heard(&other, None, "N0CALL", INTERVAL);
+        let moved = schedule.slot().unwrap() as u64;
+        assert_ne!(moved, home);
+        assert!(moved < SLOTS);
+    }
+
+    #[test]
+    fn later_callsign_in_our_slot_moves_instead() {
+        let home = home_slot("N0CALL", SLOTS);
+        let other = station_in("W", home);
+        let mut schedule = BeaconSchedule::default();
+        schedule.heard(&other, Some(home as u8), "N0CALL", INTERVAL);
+        assert_eq!(schedule.slot(), Some(home as u8));
+    }
+
+    #[test]
+    fn slot_sent_in_the_packet_counts() {
+        let home = home_slot("N0CALL", SLOTS);
+        let other = station_in("A", (home + 1) % SLOTS);
+        let mut schedule = BeaconSchedule::default();
+        // Beaconing in its home slot, out of our way
+        schedule.heard(&other, None, "N0CALL", INTERVAL);
+        assert_eq!(schedule.slot(), Some(home as u8));
+        // A slot beyond this interval's slots says nothing
+        schedule.heard(&other, Some(SLOTS as u8), "N0CALL", INTERVAL);
+        assert_eq!(schedule.slot(), Some(home as u8));
+        // Moved into ours
+        schedule.heard(&other, Some(home as u8), "N0CALL", INTERVAL);
+        assert_ne!(schedule.slot(), Some(home as u8));
+    }
+
+    #[test]
+    fn home_slot_is_worked_out_as_peers_decode_the_callsign() {
+        let mut schedule = BeaconSchedule::default();
+        schedule.heard("W1AW", None, "n0call-0", INTERVAL);
+        assert_eq!(schedule.slot(), Some(home_slot("N0CALL", SLOTS) as u8));
+    }
+}
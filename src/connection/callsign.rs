@@ -0,0 +1,53 @@
+// Callsigns as typed by players and as carried on air
+//
+// AX.25 tools write SSID 0 as the bare callsign ("K1ABC"), and so do the ax25
+// crate's Display and the packet codec, but the crate only parses "CALL-SSID".
+// Everything that addresses or compares stations goes through here, so
+// "k1abc", "K1ABC" and "K1ABC-0" are the same station everywhere.
+
+use ax25::frame::Address;
+
+// Parse a callsign in any case, with or without an SSID (0 if left off)
+pub fn parse_callsign(callsign: &str) -> Result<Address, String> {
+    let callsign = callsign.trim().to_uppercase();
+    let (base, ssid) = callsign.split_once('-').unwrap_or((&callsign, "0"));
+    format!("{}-{}", base, ssid)
+        .parse::<Address>()
+        .map_err(|_| format!("Not a callsign: {}", callsign))
+}
+
+// The form chat threads are keyed by and shown as: uppercase, SSID 0 left off.
+// Callsigns AX.25 can't carry are only uppercased.
+pub fn normalize_callsign(callsign: &str) -> String {
+    match parse_callsign(callsign) {
+        Ok(address) => address.to_string(),
+        Err(_) => callsign.trim().to_uppercase(),
+    }
+}
+
+// Whether two callsigns name the same station
+pub fn same_station(a: &str, b: &str) -> bool {
+    normalize_callsign(a) == normalize_callsign(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ssid_zero_is_optional() {
+        assert_eq!(parse_callsign("k1abc"), parse_callsign("K1ABC-0"));
+        assert_eq!(normalize_callsign("k1abc-0"), "K1ABC");
+        assert_eq!(normalize_callsign(" n0call-7 "), "N0CALL-7");
+        assert!(same_station("K1ABC", "k1abc-0"));
+        assert!(!same_station("K1ABC", "K1ABC-1"));
+    }
+
+    #[test]
+    fn rejects_what_ax25_cannot_carry() {
+        assert!(parse_callsign("AB1CDEF").is_err());
+        assert!(parse_callsign("K1ABC-16").is_err());
+        assert!(parse_callsign("K1ABC-").is_err());
+        assert_eq!(normalize_callsign("ab1cdef"), "AB1CDEF");
+    }
+}
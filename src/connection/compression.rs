@@ -1,36 +1,378 @@
+use super::message::{Ack, ChatFragment, ChatMessage};
 use crate::systems::remote_player::PlayerPositionData;
 
-// Custom packet protocol for amateur radio transmission
-// Format: {TYPE|FIELD1|FIELD2|...
+// Binary packet protocol for amateur radio transmission
+// Format: {H<VERSION><PAYLOAD>
 //
-// Uses APRS "User-Defined" data type indicator '{' for TNC compatibility
+// Uses APRS "User-Defined" data type indicator '{' with user ID 'H' (HamRPG)
+// for TNC compatibility. The version character lets clients of different
+// protocol revisions share a frequency: a packet with an unknown version is
+// rejected by decode_packet instead of being misread.
 //
-// Position: {P|CALLSIGN|X|Y|DIR
-// Example:  {P|N0CALL-1|128|256|S
+// The payload is a bit-packed header, written 13 bits at a time as two
+// printable characters (base-91), so frames stay plain 7-bit text.
 //
-// Chat:     {C|CALLSIGN|MESSAGE
-// Example:  {C|N0CALL-1|Hello world
+// Header (most significant bit first):
+//   TYPE      4 bits   0 = position, 1 = chat, 2 = hello, 3 = leave, 4 = roster query,
+//                      5 = chat fragment, 6 = ack
+//   CALLSIGN 32 bits   up to 6 characters, base-37 packed
+//   SSID      4 bits
+//
+// Position: X 12 bits, Y 12 bits (signed, COORD_STEP world units per step),
+//           DIR 2 bits (N/E/S/W), MOVING 1 bit,
+//           HEADING 3 bits (eighths of a turn counter-clockwise from east),
+//           SPEED 4 bits (SPEED_STEP world units per second, 0 = standing still),
+//           ZONE 4 bits (which map the player is on, 0 = the starting map)
+//           The velocity hint and zone fill what used to be padding, so packets
+//           from clients without them decode as standing still in zone 0.
+//           SLOT 8 bits (beacon time slot), only in beacons sent in a slot. It
+//           takes one more character pair, which clients without it skip.
+// Chat:     HAS_ID 1 bit, ACK 1 bit (please acknowledge), MSG_ID 8 bits, then
+//           the message text as-is. The ID fits in what used to be padding after
+//           the header, so older clients read the text as before and their
+//           packets decode as having no ID.
+// Fragment: MSG_ID 8 bits, INDEX 6 bits, LAST 6 bits (index of the final part),
+//           ACK 1 bit, then that part of the message text like chat. Only chat
+//           that doesn't fit in PACLEN is split; clients without fragments
+//           ignore the type.
+// Ack:      TO callsign 32 bits and SSID 4 bits, MSG_ID 8 bits: the sender of
+//           the header heard message MSG_ID from TO
+// Hello:    header only, an optional free-text profile follows like chat text
+// Leave:    header only, sent on clean exit
+// Roster:   header only, asks every station present to answer with a hello
 //
-// This is a plain-text protocol (not encryption) that is:
-// - Human readable and easily decoded
-// - APRS-compliant
+// Size for N0CALL-1 at (128, 256) facing south:
+//   legacy text  {P|N0CALL-1|128|256|S    21 bytes
+//   binary v1    {H1 + 12 characters      15 bytes (17 with a slot)
+//
+// Callsigns that do not fit the packed form (more than 6 characters, SSID
+// above 15) fall back to the legacy text format below, which is still
+// decoded so older clients remain visible:
+//
+// Position: {P|CALLSIGN|X|Y|DIR
+// Chat:     {C|CALLSIGN|MESSAGE
+// Fragment: {F|CALLSIGN|MSG_ID|INDEX|LAST|TEXT
+// Ack:      {A|CALLSIGN|TO|MSG_ID
+
+pub const PROTOCOL_VERSION: u8 = 1;
+
+const PACKET_PREFIX: &str = "{H";
+
+// World units per quantization step of a packed coordinate
+const COORD_STEP: f32 = 2.0;
+const COORD_BITS: u32 = 12;
+
+// World units per second per step of the packed speed
+const SPEED_STEP: f32 = 4.0;
+const SPEED_BITS: u32 = 4;
+const HEADING_BITS: u32 = 3;
+const ZONE_BITS: u32 = 4;
+const SLOT_BITS: u32 = 8;
 
-pub fn encode_position(callsign: &str, x: f32, y: f32, direction: &str) -> String {
+const TYPE_POSITION: u32 = 0;
+const TYPE_CHAT: u32 = 1;
+const TYPE_HELLO: u32 = 2;
+const TYPE_LEAVE: u32 = 3;
+const TYPE_ROSTER_QUERY: u32 = 4;
+const TYPE_CHAT_FRAGMENT: u32 = 5;
+const TYPE_ACK: u32 = 6;
+
+const MESSAGE_ID_BITS: u32 = 8;
+const FRAGMENT_INDEX_BITS: u32 = 6;
+// Longest message, in parts
+const MAX_FRAGMENTS: usize = 1 << FRAGMENT_INDEX_BITS;
+
+const HEADER_BITS: usize = 4 + 32 + 4;
+const POSITION_BITS: usize = HEADER_BITS + COORD_BITS as usize * 2 + 2 + 1;
+const VELOCITY_BITS: usize = (HEADING_BITS + SPEED_BITS) as usize;
+const SLOTTED_POSITION_BITS: usize = POSITION_BITS + VELOCITY_BITS + (ZONE_BITS + SLOT_BITS) as usize;
+const CHAT_BITS: usize = HEADER_BITS + 2 + MESSAGE_ID_BITS as usize;
+const FRAGMENT_BITS: usize = HEADER_BITS + (MESSAGE_ID_BITS + FRAGMENT_INDEX_BITS * 2 + 1) as usize;
+const ACK_BITS: usize = HEADER_BITS + 32 + 4 + MESSAGE_ID_BITS as usize;
+
+// 91 printable characters: '!' through '}' without '{' and '|'
+const ALPHABET: &[u8; 91] = b"!\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz}";
+
+#[allow(clippy::too_many_arguments)]
+pub fn encode_position(
+    callsign: &str,
+    x: f32,
+    y: f32,
+    direction: &str,
+    moving: bool,
+    velocity: (f32, f32),
+    zone: u8,
+    slot: Option<u8>,
+) -> String {
+    let Some(mut writer) = header(TYPE_POSITION, callsign) else {
+        return encode_position_text(callsign, x, y, direction);
+    };
+    writer.write(quantize(x), COORD_BITS);
+    writer.write(quantize(y), COORD_BITS);
     let dir_code = match direction {
-        "north" => "N",
-        "south" => "S",
-        "east" => "E",
-        "west" => "W",
-        _ => "S", // default
+        "north" => 0,
+        "east" => 1,
+        "south" => 2,
+        "west" => 3,
+        _ => 2, // default
     };
-    format!("{{P|{}|{}|{}|{}", callsign, x.round() as i32, y.round() as i32, dir_code)
+    writer.write(dir_code, 2);
+    writer.write(moving as u32, 1);
+    let (heading, speed) = quantize_velocity(velocity);
+    writer.write(heading, HEADING_BITS);
+    writer.write(speed, SPEED_BITS);
+    writer.write(zone as u32 & ((1 << ZONE_BITS) - 1), ZONE_BITS);
+    if let Some(slot) = slot {
+        writer.write(slot as u32, SLOT_BITS);
+    }
+    format!("{}{}{}", PACKET_PREFIX, PROTOCOL_VERSION, writer.finish())
+}
+
+fn encode_chat(callsign: &str, id: u8, ack: bool, message: &str) -> String {
+    let Some(mut writer) = header(TYPE_CHAT, callsign) else {
+        return format!("{{C|{}|{}", callsign, message);
+    };
+    writer.write(1, 1);
+    writer.write(ack as u32, 1);
+    writer.write(id as u32, MESSAGE_ID_BITS);
+    format!("{}{}{}{}", PACKET_PREFIX, PROTOCOL_VERSION, writer.finish(), message)
+}
+
+// Chat as frames of at most `paclen` bytes. A message that fits goes out as a
+// single chat packet; a longer one is split into numbered parts of message `id`.
+// With `ack` set, receivers are asked to acknowledge it.
+pub fn encode_chat_frames(callsign: &str, id: u8, ack: bool, message: &str, paclen: usize) -> Vec<String> {
+    let whole = encode_chat(callsign, id, ack, message);
+    if whole.len() <= paclen {
+        return vec![whole];
+    }
+
+    let overhead = encode_fragment(callsign, id, ack, MAX_FRAGMENTS - 1, MAX_FRAGMENTS - 1, "").len();
+    let mut parts = split_text(message, paclen.saturating_sub(overhead).max(1));
+    if parts.len() > MAX_FRAGMENTS {
+        println!("[!] Chat message too long, sending the first {} parts", MAX_FRAGMENTS);
+        parts.truncate(MAX_FRAGMENTS);
+    }
+    let last = parts.len() - 1;
+    parts
+        .iter()
+        .enumerate()
+        .map(|(index, part)| encode_fragment(callsign, id, ack, index, last, part))
+        .collect()
+}
+
+fn encode_fragment(callsign: &str, id: u8, ack: bool, index: usize, last: usize, text: &str) -> String {
+    let Some(mut writer) = header(TYPE_CHAT_FRAGMENT, callsign) else {
+        return format!("{{F|{}|{}|{}|{}|{}", callsign, id, index, last, text);
+    };
+    writer.write(id as u32, MESSAGE_ID_BITS);
+    writer.write(index as u32, FRAGMENT_INDEX_BITS);
+    writer.write(last as u32, FRAGMENT_INDEX_BITS);
+    writer.write(ack as u32, 1);
+    format!("{}{}{}{}", PACKET_PREFIX, PROTOCOL_VERSION, writer.finish(), text)
+}
+
+// Tell `to` we heard its message `id`
+pub fn encode_ack(callsign: &str, to: &str, id: u8) -> String {
+    let (Some(mut writer), Some((packed, ssid))) = (header(TYPE_ACK, callsign), pack_callsign(to)) else {
+        return format!("{{A|{}|{}|{}", callsign, to, id);
+    };
+    writer.write(packed, 32);
+    writer.write(ssid, 4);
+    writer.write(id as u32, MESSAGE_ID_BITS);
+    format!("{}{}{}", PACKET_PREFIX, PROTOCOL_VERSION, writer.finish())
+}
+
+// Whether chat from this callsign carries message IDs, which acknowledgements
+// need. Callsigns that fall back to the text format can't be acknowledged.
+pub fn can_acknowledge(callsign: &str) -> bool {
+    pack_callsign(callsign).is_some()
+}
+
+// Cut text into pieces of at most `max_bytes`, without splitting a character
+fn split_text(text: &str, max_bytes: usize) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let mut end = rest.len().min(max_bytes);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        if end == 0 {
+            // A single character wider than the limit still has to go somewhere
+            end = rest.chars().next().map_or(rest.len(), char::len_utf8);
+        }
+        let (part, tail) = rest.split_at(end);
+        parts.push(part);
+        rest = tail;
+    }
+    parts
 }
 
-pub fn encode_chat(callsign: &str, message: &str) -> String {
-    format!("{{C|{}|{}", callsign, message)
+// Announce ourselves, optionally with a short free-text profile
+pub fn encode_hello(callsign: &str, profile: Option<&str>) -> Option<String> {
+    let writer = header(TYPE_HELLO, callsign)?;
+    Some(format!(
+        "{}{}{}{}",
+        PACKET_PREFIX,
+        PROTOCOL_VERSION,
+        writer.finish(),
+        profile.unwrap_or("")
+    ))
+}
+
+pub fn encode_leave(callsign: &str) -> Option<String> {
+    let writer = header(TYPE_LEAVE, callsign)?;
+    Some(format!("{}{}{}", PACKET_PREFIX, PROTOCOL_VERSION, writer.finish()))
+}
+
+pub fn encode_roster_query(callsign: &str) -> Option<String> {
+    let writer = header(TYPE_ROSTER_QUERY, callsign)?;
+    Some(format!("{}{}{}", PACKET_PREFIX, PROTOCOL_VERSION, writer.finish()))
 }
 
 pub fn decode_packet(data: &str) -> Result<DecodedPacket, String> {
+    match data.strip_prefix(PACKET_PREFIX) {
+        Some(versioned) => decode_binary(versioned),
+        None => decode_text(data),
+    }
+}
+
+pub enum DecodedPacket {
+    Position(PlayerPositionData),
+    Chat(ChatMessage),
+    ChatFragment(ChatFragment),
+    Ack(Ack),
+    Hello {
+        callsign: String,
+        profile: Option<String>,
+    },
+    Leave {
+        callsign: String,
+    },
+    RosterQuery {
+        callsign: String,
+    },
+}
+
+fn decode_binary(data: &str) -> Result<DecodedPacket, String> {
+    let mut chars = data.chars();
+    let version = chars.next().ok_or("Missing protocol version")?;
+    if version.to_digit(10) != Some(PROTOCOL_VERSION as u32) {
+        return Err(format!("Unsupported protocol version: {}", version));
+    }
+    let payload = chars.as_str();
+
+    let mut reader = BitReader::new(payload);
+    let packet_type = reader.read(4).ok_or("Truncated packet")?;
+    let callsign = unpack_callsign(
+        reader.read(32).ok_or("Truncated packet")?,
+        reader.read(4).ok_or("Truncated packet")?,
+    );
+
+    match packet_type {
+        TYPE_POSITION => {
+            reader.require(POSITION_BITS).ok_or("Invalid position packet")?;
+            let x = dequantize(reader.read(COORD_BITS).ok_or("Invalid X coordinate")?);
+            let y = dequantize(reader.read(COORD_BITS).ok_or("Invalid Y coordinate")?);
+            let direction = match reader.read(2).ok_or("Invalid direction")? {
+                0 => "north",
+                1 => "east",
+                3 => "west",
+                _ => "south",
+            }
+            .to_string();
+            let moving = reader.read(1).ok_or("Invalid position packet")? == 1;
+            // Optional: absent from packets that end right after MOVING
+            let (vx, vy) = match reader.require(POSITION_BITS + VELOCITY_BITS) {
+                Some(()) => dequantize_velocity(
+                    reader.read(HEADING_BITS).unwrap_or(0),
+                    reader.read(SPEED_BITS).unwrap_or(0),
+                ),
+                None => (0.0, 0.0),
+            };
+            let zone = match reader.require(POSITION_BITS + VELOCITY_BITS + ZONE_BITS as usize) {
+                Some(()) => reader.read(ZONE_BITS).unwrap_or(0) as u8,
+                None => 0,
+            };
+            let slot = match reader.require(SLOTTED_POSITION_BITS) {
+                Some(()) => reader.read(SLOT_BITS).map(|slot| slot as u8),
+                None => None,
+            };
+
+            Ok(DecodedPacket::Position(PlayerPositionData {
+                callsign,
+                x,
+                y,
+                direction,
+                moving,
+                vx,
+                vy,
+                zone,
+                slot,
+            }))
+        }
+        TYPE_CHAT => {
+            let message = payload
+                .get(encoded_len(HEADER_BITS)..)
+                .ok_or("Invalid chat packet")?;
+            // Older clients leave HAS_ID clear
+            let (id, ack) = match reader.require(CHAT_BITS).and(reader.read(1)) {
+                Some(1) => {
+                    let ack = reader.read(1).ok_or("Invalid chat packet")? == 1;
+                    let id = reader.read(MESSAGE_ID_BITS).ok_or("Invalid chat packet")? as u8;
+                    (Some(id), ack)
+                }
+                _ => (None, false),
+            };
+            Ok(DecodedPacket::Chat(ChatMessage {
+                callsign,
+                id,
+                ack,
+                text: message.to_string(),
+            }))
+        }
+        TYPE_CHAT_FRAGMENT => {
+            reader.require(FRAGMENT_BITS).ok_or("Invalid fragment packet")?;
+            let id = reader.read(MESSAGE_ID_BITS).ok_or("Invalid fragment packet")? as u8;
+            let index = reader.read(FRAGMENT_INDEX_BITS).ok_or("Invalid fragment packet")? as u8;
+            let last = reader.read(FRAGMENT_INDEX_BITS).ok_or("Invalid fragment packet")? as u8;
+            let ack = reader.read(1).ok_or("Invalid fragment packet")? == 1;
+            let text = payload
+                .get(encoded_len(FRAGMENT_BITS)..)
+                .ok_or("Invalid fragment packet")?;
+            fragment(callsign, id, index, last, ack, text)
+        }
+        TYPE_ACK => {
+            reader.require(ACK_BITS).ok_or("Invalid ack packet")?;
+            let to = unpack_callsign(
+                reader.read(32).ok_or("Invalid ack packet")?,
+                reader.read(4).ok_or("Invalid ack packet")?,
+            );
+            let id = reader.read(MESSAGE_ID_BITS).ok_or("Invalid ack packet")? as u8;
+            Ok(DecodedPacket::Ack(Ack {
+                from: callsign,
+                to,
+                id,
+            }))
+        }
+        TYPE_HELLO => {
+            let profile = payload
+                .get(encoded_len(HEADER_BITS)..)
+                .ok_or("Invalid hello packet")?
+                .trim();
+            Ok(DecodedPacket::Hello {
+                callsign,
+                profile: (!profile.is_empty()).then(|| profile.to_string()),
+            })
+        }
+        TYPE_LEAVE => Ok(DecodedPacket::Leave { callsign }),
+        TYPE_ROSTER_QUERY => Ok(DecodedPacket::RosterQuery { callsign }),
+        _ => Err(format!("Unknown packet type: {}", packet_type)),
+    }
+}
+
+fn decode_text(data: &str) -> Result<DecodedPacket, String> {
     // Remove APRS user-defined prefix if present
     let data = data.strip_prefix('{').unwrap_or(data);
 
@@ -62,6 +404,11 @@ pub fn decode_packet(data: &str) -> Result<DecodedPacket, String> {
                 x,
                 y,
                 direction,
+                moving: false,
+                vx: 0.0,
+                vy: 0.0,
+                zone: 0,
+                slot: None,
             }))
         }
         "C" => {
@@ -72,13 +419,342 @@ pub fn decode_packet(data: &str) -> Result<DecodedPacket, String> {
             let callsign = parts[1].to_string();
             // Rejoin remaining parts in case message contains '|'
             let message = parts[2..].join("|");
-            Ok(DecodedPacket::Chat(format!("{}: {}", callsign, message)))
+            Ok(DecodedPacket::Chat(ChatMessage {
+                callsign,
+                id: None,
+                ack: false,
+                text: message,
+            }))
+        }
+        "F" => {
+            // Fragment packet: {F|CALLSIGN|MSG_ID|INDEX|LAST|TEXT
+            if parts.len() < 6 {
+                return Err("Invalid fragment packet".to_string());
+            }
+            let number = |part: &str| part.parse::<u8>().map_err(|_| "Invalid fragment number");
+            let text = parts[5..].join("|");
+            fragment(
+                parts[1].to_string(),
+                number(parts[2])?,
+                number(parts[3])?,
+                number(parts[4])?,
+                false,
+                &text,
+            )
+        }
+        "A" => {
+            // Ack packet: {A|CALLSIGN|TO|MSG_ID
+            if parts.len() < 4 {
+                return Err("Invalid ack packet".to_string());
+            }
+            Ok(DecodedPacket::Ack(Ack {
+                from: parts[1].to_string(),
+                to: parts[2].to_string(),
+                id: parts[3].parse::<u8>().map_err(|_| "Invalid message ID")?,
+            }))
         }
         _ => Err(format!("Unknown packet type: {}", parts[0])),
     }
 }
 
-pub enum DecodedPacket {
-    Position(PlayerPositionData),
-    Chat(String),
+fn fragment(
+    callsign: String,
+    id: u8,
+    index: u8,
+    last: u8,
+    ack: bool,
+    text: &str,
+) -> Result<DecodedPacket, String> {
+    if index > last || last as usize >= MAX_FRAGMENTS {
+        return Err(format!("Invalid fragment {} of {}", index, last));
+    }
+    Ok(DecodedPacket::ChatFragment(ChatFragment {
+        callsign,
+        id,
+        index,
+        last,
+        ack,
+        text: text.to_string(),
+    }))
+}
+
+fn encode_position_text(callsign: &str, x: f32, y: f32, direction: &str) -> String {
+    let dir_code = match direction {
+        "north" => "N",
+        "south" => "S",
+        "east" => "E",
+        "west" => "W",
+        _ => "S", // default
+    };
+    format!("{{P|{}|{}|{}|{}", callsign, x.round() as i32, y.round() as i32, dir_code)
+}
+
+// Start a packet with the common TYPE/CALLSIGN/SSID header.
+// Returns None if the callsign cannot be packed.
+fn header(packet_type: u32, callsign: &str) -> Option<BitWriter> {
+    let (packed, ssid) = pack_callsign(callsign)?;
+    let mut writer = BitWriter::default();
+    writer.write(packet_type, 4);
+    writer.write(packed, 32);
+    writer.write(ssid, 4);
+    Some(writer)
+}
+
+// Base-37 packing: 0 is padding, then '0'-'9', then 'A'-'Z'
+fn pack_callsign(callsign: &str) -> Option<(u32, u32)> {
+    let (base, ssid) = match callsign.split_once('-') {
+        Some((base, ssid)) => (base, ssid.parse::<u32>().ok()?),
+        None => (callsign, 0),
+    };
+    if base.is_empty() || base.len() > 6 || ssid > 15 {
+        return None;
+    }
+
+    let mut packed: u32 = 0;
+    for i in 0..6 {
+        let digit = match base.as_bytes().get(i).map(u8::to_ascii_uppercase) {
+            None => 0,
+            Some(c @ b'0'..=b'9') => (c - b'0') as u32 + 1,
+            Some(c @ b'A'..=b'Z') => (c - b'A') as u32 + 11,
+            Some(_) => return None,
+        };
+        packed = packed * 37 + digit;
+    }
+    Some((packed, ssid))
+}
+
+fn unpack_callsign(mut packed: u32, ssid: u32) -> String {
+    let mut chars = [0u8; 6];
+    for c in chars.iter_mut().rev() {
+        *c = match packed % 37 {
+            0 => 0,
+            d @ 1..=10 => b'0' + (d - 1) as u8,
+            d => b'A' + (d - 11) as u8,
+        };
+        packed /= 37;
+    }
+    let base: String = chars.iter().filter(|&&c| c != 0).map(|&c| c as char).collect();
+    if ssid == 0 {
+        base
+    } else {
+        format!("{}-{}", base, ssid)
+    }
+}
+
+fn quantize(value: f32) -> u32 {
+    let limit = 1 << (COORD_BITS - 1);
+    let steps = ((value / COORD_STEP).round() as i32).clamp(-limit, limit - 1);
+    (steps as u32) & ((1 << COORD_BITS) - 1)
+}
+
+fn dequantize(bits: u32) -> f32 {
+    // Sign-extend the COORD_BITS-wide field
+    let shift = 32 - COORD_BITS;
+    let steps = ((bits << shift) as i32) >> shift;
+    steps as f32 * COORD_STEP
+}
+
+// Pack a velocity into a heading eighth and a speed step
+fn quantize_velocity((vx, vy): (f32, f32)) -> (u32, u32) {
+    let max_speed = ((1 << SPEED_BITS) - 1) as f32;
+    let speed = ((vx * vx + vy * vy).sqrt() / SPEED_STEP).round().min(max_speed) as u32;
+    if speed == 0 {
+        return (0, 0);
+    }
+    let eighths = (vy.atan2(vx) / std::f32::consts::FRAC_PI_4).round() as i32;
+    (eighths.rem_euclid(8) as u32, speed)
+}
+
+fn dequantize_velocity(heading: u32, speed: u32) -> (f32, f32) {
+    let angle = heading as f32 * std::f32::consts::FRAC_PI_4;
+    let speed = speed as f32 * SPEED_STEP;
+    (angle.cos() * speed, angle.sin() * speed)
+}
+
+// Number of characters needed to carry the given number of bits
+fn encoded_len(bits: usize) -> usize {
+    bits.div_ceil(13) * 2
+}
+
+// Accumulates bit fields and encodes them 13 bits per character pair
+#[derive(Default)]
+struct BitWriter {
+    bits: Vec<bool>,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, width: u32) {
+        for i in (0..width).rev() {
+            self.bits.push((value >> i) & 1 == 1);
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut out = String::with_capacity(encoded_len(self.bits.len()));
+        for group in self.bits.chunks(13) {
+            let mut value: usize = 0;
+            for i in 0..13 {
+                value = (value << 1) | group.get(i).copied().unwrap_or(false) as usize;
+            }
+            out.push(ALPHABET[value % 91] as char);
+            out.push(ALPHABET[value / 91] as char);
+        }
+        out
+    }
+}
+
+// Reads bit fields back out of base-91 character pairs
+struct BitReader {
+    bits: Vec<bool>,
+    pos: usize,
+}
+
+impl BitReader {
+    fn new(encoded: &str) -> Self {
+        let mut bits = Vec::new();
+        let bytes = encoded.as_bytes();
+        for pair in bytes.chunks_exact(2) {
+            let (Some(low), Some(high)) = (alphabet_index(pair[0]), alphabet_index(pair[1])) else {
+                // Anything after the encoded header (e.g. chat text) is not ours to read
+                break;
+            };
+            let value = high * 91 + low;
+            if value >= 1 << 13 {
+                break;
+            }
+            for i in (0..13).rev() {
+                bits.push((value >> i) & 1 == 1);
+            }
+        }
+        Self { bits, pos: 0 }
+    }
+
+    fn read(&mut self, width: u32) -> Option<u32> {
+        let end = self.pos + width as usize;
+        let field = self.bits.get(self.pos..end)?;
+        self.pos = end;
+        Some(field.iter().fold(0, |acc, &bit| (acc << 1) | bit as u32))
+    }
+
+    // Check that the packet carries at least `total` bits
+    fn require(&self, total: usize) -> Option<()> {
+        (self.bits.len() >= total).then_some(())
+    }
+}
+
+fn alphabet_index(c: u8) -> Option<usize> {
+    ALPHABET.iter().position(|&a| a == c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_position(packet: &str) -> PlayerPositionData {
+        match decode_packet(packet) {
+            Ok(DecodedPacket::Position(position)) => position,
+            _ => panic!("not a position packet: {}", packet),
+        }
+    }
+
+    fn decode_chat(packet: &str) -> ChatMessage {
+        match decode_packet(packet) {
+            Ok(DecodedPacket::Chat(chat)) => chat,
+            _ => panic!("not a chat packet: {}", packet),
+        }
+    }
+
+    #[test]
+    fn position_round_trip() {
+        let packet = encode_position("N0CALL-1", 128.0, 256.0, "west", true, (16.0, 0.0), 3, None);
+        assert!(packet.starts_with("{H1"));
+        let position = decode_position(&packet);
+        assert_eq!(position.callsign, "N0CALL-1");
+        assert_eq!((position.x, position.y), (128.0, 256.0));
+        assert_eq!(position.direction, "west");
+        assert!(position.moving);
+        assert_eq!((position.vx.round(), position.vy.round()), (16.0, 0.0));
+        assert_eq!(position.zone, 3);
+        assert_eq!(position.slot, None);
+    }
+
+    #[test]
+    fn position_carries_beacon_slot() {
+        let packet =
+            encode_position("N0CALL-1", 128.0, 256.0, "west", true, (16.0, 0.0), 3, Some(200));
+        assert_eq!(packet.len(), 17);
+        let position = decode_position(&packet);
+        assert_eq!(position.slot, Some(200));
+        assert_eq!((position.x, position.y, position.zone), (128.0, 256.0, 3));
+    }
+
+    #[test]
+    fn position_negative_and_clamped_coordinates() {
+        let packet = encode_position("K1ABC", -101.0, -2.0, "north", false, (0.0, 0.0), 0, None);
+        let position = decode_position(&packet);
+        assert_eq!((position.x, position.y), (-102.0, -2.0));
+
+        let packet = encode_position("K1ABC", 1.0e6, -1.0e6, "south", false, (0.0, 0.0), 0, None);
+        let position = decode_position(&packet);
+        let limit = (1 << (COORD_BITS - 1)) as f32 * COORD_STEP;
+        assert_eq!((position.x, position.y), (limit - COORD_STEP, -limit));
+    }
+
+    #[test]
+    fn ssid_range() {
+        // SSID 0 is left off, as AX.25 tools write it
+        let packet = encode_position("K1ABC-0", 0.0, 0.0, "east", false, (0.0, 0.0), 0, None);
+        assert_eq!(decode_position(&packet).callsign, "K1ABC");
+        let packet = encode_position("K1ABC-15", 0.0, 0.0, "east", false, (0.0, 0.0), 0, None);
+        let position = decode_position(&packet);
+        assert_eq!(position.callsign, "K1ABC-15");
+    }
+
+    #[test]
+    fn chat_round_trip() {
+        let chat = decode_chat(&encode_chat("N0CALL-15", 42, true, "hello | world"));
+        assert_eq!(chat.callsign, "N0CALL-15");
+        assert_eq!(chat.id, Some(42));
+        assert!(chat.ack);
+        assert_eq!(chat.text, "hello | world");
+
+        let chat = decode_chat(&encode_chat("K1ABC", 0, false, ""));
+        assert_eq!(chat.callsign, "K1ABC");
+        assert_eq!((chat.id, chat.ack, chat.text.as_str()), (Some(0), false, ""));
+    }
+
+    #[test]
+    fn long_callsign_falls_back_to_text() {
+        let packet = encode_position("AB1CDEF", -10.4, 20.6, "north", false, (0.0, 0.0), 0, None);
+        assert_eq!(packet, "{P|AB1CDEF|-10|21|N");
+        let position = decode_position(&packet);
+        assert_eq!(position.callsign, "AB1CDEF");
+        assert_eq!((position.x, position.y), (-10.0, 21.0));
+        assert_eq!(position.direction, "north");
+
+        let chat = decode_chat(&encode_chat("AB1CDEF", 7, true, "a|b"));
+        assert_eq!(chat.callsign, "AB1CDEF");
+        assert_eq!((chat.id, chat.ack, chat.text.as_str()), (None, false, "a|b"));
+    }
+
+    #[test]
+    fn high_ssid_falls_back_to_text() {
+        let packet = encode_position("N0CALL-16", 64.0, 32.0, "east", false, (0.0, 0.0), 0, None);
+        assert!(packet.starts_with("{P|"));
+        let position = decode_position(&packet);
+        assert_eq!(position.callsign, "N0CALL-16");
+        assert_eq!((position.x, position.y), (64.0, 32.0));
+
+        let chat = decode_chat(&encode_chat("N0CALL-16", 7, false, "hi"));
+        assert_eq!(chat.callsign, "N0CALL-16");
+        assert_eq!(chat.text, "hi");
+    }
+
+    #[test]
+    fn binary_position_is_shorter_than_text() {
+        let binary = encode_position("N0CALL-1", 128.0, 256.0, "south", false, (0.0, 0.0), 0, None);
+        let text = encode_position_text("N0CALL-1", 128.0, 256.0, "south");
+        assert_eq!(text, "{P|N0CALL-1|128|256|S");
+        assert_eq!((binary.len(), text.len()), (15, 21));
+    }
 }
@@ -0,0 +1,295 @@
+// Acknowledged chat delivery
+//
+// Chat sent with the ACK flag is kept until a station acknowledges it, and sent
+// again with a growing delay until one does or we run out of tries. Its line in
+// the chat window shows a tick and who heard it, or that nobody did.
+//
+// Receivers acknowledge after a short random delay, so several stations
+// answering a broadcast don't all key up at once. They remember the messages
+// they have shown for a while, so a retry is acknowledged again but not shown
+// twice. Direct messages are tracked the same way, and resent to their station
+// only.
+
+use super::compression::encode_ack;
+use super::message::MessageType;
+use super::resources::ListenOnly;
+use super::tnc_integration::GameState;
+use super::tnc_plugin::TncOutgoingEvent;
+use bevy::prelude::*;
+use rand::{Rng, rng};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+// First retry after this long, doubling after each try
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(30);
+// Sends before giving up, the first one included
+const MAX_ATTEMPTS: u32 = 4;
+// Receivers wait a random time in this range (seconds) before acknowledging
+const ACK_DELAY: std::ops::RangeInclusive<f32> = 0.5..=4.0;
+// How long a shown message is remembered, to spot retries of it
+const SEEN_FOR: Duration = Duration::from_secs(600);
+// Stop collecting ACKs for a delivered message after this long
+const DELIVERED_FOR: Duration = Duration::from_secs(300);
+
+// Marks after our own chat lines
+const PENDING_MARK: &str = " …";
+const DELIVERED_MARK: &str = " ✔";
+const FAILED_MARK: &str = " (not acknowledged)";
+
+struct OutgoingMessage {
+    frames: Vec<String>,
+    message_type: MessageType,
+    // Station a direct message is for, whose conversation holds the line
+    destination: Option<String>,
+    // The chat line as first shown, without any mark
+    line: String,
+    // The chat line as shown now
+    shown: String,
+    attempts: u32,
+    next_try: Instant,
+    acked_by: Vec<String>,
+}
+
+#[derive(Resource, Default)]
+pub struct ChatDelivery {
+    // Our messages waiting for, or collecting, ACKs by message ID
+    outgoing: BTreeMap<u8, OutgoingMessage>,
+    // ACKs to send: to whom, for which message, and when
+    acks: Vec<(String, u8, Instant)>,
+    // Messages shown recently by sender and ID, with their text
+    seen: HashMap<(String, u8), (String, Instant)>,
+}
+
+impl ChatDelivery {
+    // Keep a message that asked for ACKs, whose frames were just sent and whose
+    // line was just added to the chat. Returns the line to show instead.
+    pub fn track(
+        &mut self,
+        id: u8,
+        frames: Vec<String>,
+        message_type: MessageType,
+        destination: Option<String>,
+        line: String,
+    ) -> String {
+        let shown = format!("{}{}", line, PENDING_MARK);
+        self.outgoing.insert(
+            id,
+            OutgoingMessage {
+                frames,
+                message_type,
+                destination,
+                line,
+                shown: shown.clone(),
+                attempts: 1,
+                next_try: Instant::now() + RETRY_BASE_DELAY,
+                acked_by: Vec::new(),
+            },
+        );
+        shown
+    }
+
+    // Acknowledge message `id` from `callsign` after a short delay
+    pub fn queue_ack(&mut self, callsign: &str, id: u8) {
+        // A retry may arrive before our first ACK went out
+        if self.acks.iter().any(|(to, ack_id, _)| to == callsign && *ack_id == id) {
+            return;
+        }
+        let due = Instant::now() + Duration::from_secs_f32(rng().random_range(ACK_DELAY));
+        self.acks.push((callsign.to_string(), id, due));
+    }
+
+    // Whether this message was shown already, i.e. this is a retry. Remembers
+    // it otherwise. A reused ID with different text is a new message.
+    pub fn seen_before(&mut self, callsign: &str, id: u8, text: &str) -> bool {
+        let now = Instant::now();
+        self.seen.retain(|_, (_, when)| now.duration_since(*when) < SEEN_FOR);
+        let key = (callsign.to_string(), id);
+        if self.seen.get(&key).is_some_and(|(seen, _)| seen == text) {
+            return true;
+        }
+        self.seen.insert(key, (text.to_string(), now));
+        false
+    }
+
+    // Whether a message from `callsign` with this ID was shown recently
+    pub fn shown_recently(&self, callsign: &str, id: u8) -> bool {
+        self.seen
+            .get(&(callsign.to_string(), id))
+            .is_some_and(|(_, when)| when.elapsed() < SEEN_FOR)
+    }
+
+    // `from` heard our message `id`: stop retrying and tick its line
+    pub fn acknowledged(&mut self, id: u8, from: &str, game_state: &mut GameState) {
+        let Some(message) = self.outgoing.get_mut(&id) else {
+            return;
+        };
+        if message.acked_by.iter().any(|callsign| callsign == from) {
+            return;
+        }
+        println!("[i] {} acknowledged message {}", from, id);
+        message.acked_by.push(from.to_string());
+        // Keep collecting ACKs from other stations for a while
+        message.next_try = Instant::now() + DELIVERED_FOR;
+        let shown = format!("{}{} {}", message.line, DELIVERED_MARK, message.acked_by.join(", "));
+        replace_line(game_state, message.destination.as_deref(), &message.shown, &shown);
+        message.shown = shown;
+    }
+
+    // ACKs due to be sent by `now`, as the station and message ID to acknowledge
+    fn due_acks(&mut self, now: Instant) -> Vec<(String, u8)> {
+        let (due, waiting): (Vec<_>, Vec<_>) = self.acks.drain(..).partition(|(_, _, when)| *when <= now);
+        self.acks = waiting;
+        due.into_iter().map(|(to, id, _)| (to, id)).collect()
+    }
+
+    // Frames of unacknowledged messages due to be sent again by `now`. Messages
+    // tried often enough are given up and their lines marked.
+    fn retries(&mut self, now: Instant, game_state: &mut GameState) -> Vec<TncOutgoingEvent> {
+        let mut resend = Vec::new();
+        let mut finished = Vec::new();
+        for (&id, message) in self.outgoing.iter_mut() {
+            if now < message.next_try {
+                continue;
+            }
+            if !message.acked_by.is_empty() {
+                finished.push(id);
+            } else if message.attempts >= MAX_ATTEMPTS {
+                println!("[!] No ACK for message {} after {} tries", id, message.attempts);
+                let shown = format!("{}{}", message.line, FAILED_MARK);
+                replace_line(game_state, message.destination.as_deref(), &message.shown, &shown);
+                finished.push(id);
+            } else {
+                message.attempts += 1;
+                println!("[i] Resending message {} (try {} of {})", id, message.attempts, MAX_ATTEMPTS);
+                resend.extend(message.frames.iter().map(|frame| TncOutgoingEvent {
+                    message: frame.clone(),
+                    message_type: message.message_type.clone(),
+                    destination: message.destination.clone(),
+                }));
+                message.next_try = now + RETRY_BASE_DELAY * (1 << (message.attempts - 1));
+            }
+        }
+        for id in finished {
+            self.outgoing.remove(&id);
+        }
+        resend
+    }
+}
+
+// Swap the most recent line reading `old` for `new`, in the main chat or in the
+// conversation with a station
+fn replace_line(game_state: &mut GameState, thread: Option<&str>, old: &str, new: &str) {
+    let lines = game_state.chat_lines_mut(thread);
+    if let Some(line) = lines.iter_mut().rev().find(|line| *line == old) {
+        *line = new.to_string();
+    }
+}
+
+// Send ACKs that are due, resend unacknowledged chat, and give up on chat that
+// has been tried often enough
+pub fn update_chat_delivery(
+    mut delivery: ResMut<ChatDelivery>,
+    mut event_writer: EventWriter<TncOutgoingEvent>,
+    mut game_state: ResMut<GameState>,
+    listen_only: Res<ListenOnly>,
+) {
+    let now = Instant::now();
+
+    let due = delivery.due_acks(now);
+    // Listeners never transmit, not even ACKs
+    if !listen_only.0 {
+        for (to, id) in due {
+            println!("[i] Acknowledging message {} from {}", id, to);
+            event_writer.write(TncOutgoingEvent {
+                message: encode_ack(&game_state.player_callsign, &to, id),
+                message_type: MessageType::Ack,
+                destination: None,
+            });
+        }
+    }
+
+    for frame in delivery.retries(now, &mut game_state) {
+        event_writer.write(frame);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A message sent to everyone as two frames, shown in the main chat
+    fn send(delivery: &mut ChatDelivery, game_state: &mut GameState, id: u8) {
+        let frames = vec!["part 1".to_string(), "part 2".to_string()];
+        let line = "N0CALL: hello".to_string();
+        let shown = delivery.track(id, frames, MessageType::ChatFragment, None, line);
+        game_state.chat_lines_mut(None).push(shown);
+    }
+
+    #[test]
+    fn retries_back_off_then_give_up() {
+        let (mut delivery, mut game_state) = (ChatDelivery::default(), GameState::default());
+        let start = Instant::now();
+        send(&mut delivery, &mut game_state, 5);
+        assert_eq!(game_state.chat_messages, ["N0CALL: hello …"]);
+
+        let mut now = start + RETRY_BASE_DELAY;
+        for delay in [60, 120, 240] {
+            assert!(delivery.retries(now - Duration::from_secs(1), &mut game_state).is_empty());
+            now += Duration::from_secs(1);
+            let resent = delivery.retries(now, &mut game_state);
+            let frames: Vec<_> = resent.iter().map(|event| event.message.as_str()).collect();
+            assert_eq!(frames, ["part 1", "part 2"]);
+            now += Duration::from_secs(delay);
+        }
+
+        // The fourth send was the last
+        assert!(delivery.retries(now + Duration::from_secs(1), &mut game_state).is_empty());
+        assert_eq!(game_state.chat_messages, ["N0CALL: hello (not acknowledged)"]);
+        assert!(delivery.outgoing.is_empty());
+    }
+
+    #[test]
+    fn acknowledged_message_is_ticked_and_not_resent() {
+        let (mut delivery, mut game_state) = (ChatDelivery::default(), GameState::default());
+        let start = Instant::now();
+        send(&mut delivery, &mut game_state, 9);
+        delivery.acknowledged(9, "K1ABC", &mut game_state);
+        delivery.acknowledged(9, "K1ABC", &mut game_state);
+        delivery.acknowledged(9, "W1AW", &mut game_state);
+        // An ACK for a message we don't have changes nothing
+        delivery.acknowledged(10, "W1AW", &mut game_state);
+        assert_eq!(game_state.chat_messages, ["N0CALL: hello ✔ K1ABC, W1AW"]);
+
+        let later = start + RETRY_BASE_DELAY * 2;
+        assert!(delivery.retries(later, &mut game_state).is_empty());
+        assert!(delivery.retries(later + DELIVERED_FOR, &mut game_state).is_empty());
+        assert!(delivery.outgoing.is_empty());
+        assert_eq!(game_state.chat_messages, ["N0CALL: hello ✔ K1ABC, W1AW"]);
+    }
+
+    #[test]
+    fn acks_wait_a_moment_and_are_sent_once() {
+        let mut delivery = ChatDelivery::default();
+        let start = Instant::now();
+        delivery.queue_ack("K1ABC", 1);
+        // A retry arriving before our ACK went out
+        delivery.queue_ack("K1ABC", 1);
+        delivery.queue_ack("W1AW", 1);
+        assert!(delivery.due_acks(start).is_empty());
+        let due = delivery.due_acks(start + Duration::from_secs(5));
+        assert_eq!(due, [("K1ABC".to_string(), 1), ("W1AW".to_string(), 1)]);
+        assert!(delivery.due_acks(start + Duration::from_secs(10)).is_empty());
+    }
+
+    #[test]
+    fn repeats_are_spotted_by_sender_id_and_text() {
+        let mut delivery = ChatDelivery::default();
+        assert!(!delivery.seen_before("K1ABC", 3, "hello"));
+        assert!(delivery.seen_before("K1ABC", 3, "hello"));
+        assert!(delivery.shown_recently("K1ABC", 3));
+        // Same ID from another station, or reused for new text
+        assert!(!delivery.seen_before("W1AW", 3, "hello"));
+        assert!(!delivery.seen_before("K1ABC", 3, "goodbye"));
+        assert!(!delivery.shown_recently("K1ABC", 4));
+    }
+}
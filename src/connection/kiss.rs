@@ -0,0 +1,115 @@
+// KISS framing (FEND/FESC byte stuffing) for transports that talk to a modem
+// over a raw byte stream instead of through ax25_tnc
+
+pub const FEND: u8 = 0xC0;
+pub const FESC: u8 = 0xDB;
+pub const TFEND: u8 = 0xDC;
+pub const TFESC: u8 = 0xDD;
+
+// KISS command byte for a data frame on port 0
+const CMD_DATA: u8 = 0x00;
+
+// Wrap an AX.25 frame as a KISS data frame on port 0
+pub fn encode_frame(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + 4);
+    out.push(FEND);
+    out.push(CMD_DATA);
+    for &byte in data {
+        match byte {
+            FEND => out.extend_from_slice(&[FESC, TFEND]),
+            FESC => out.extend_from_slice(&[FESC, TFESC]),
+            _ => out.push(byte),
+        }
+    }
+    out.push(FEND);
+    out
+}
+
+// Incremental decoder that turns a KISS byte stream back into frames
+#[derive(Default)]
+pub struct KissDecoder {
+    buffer: Vec<u8>,
+    escaped: bool,
+}
+
+impl KissDecoder {
+    // Feed one byte; returns the frame (command byte included) when a FEND closes it
+    pub fn push(&mut self, byte: u8) -> Option<Vec<u8>> {
+        match byte {
+            FEND => {
+                self.escaped = false;
+                if self.buffer.is_empty() {
+                    None
+                } else {
+                    Some(std::mem::take(&mut self.buffer))
+                }
+            }
+            FESC => {
+                self.escaped = true;
+                None
+            }
+            _ => {
+                let byte = match (self.escaped, byte) {
+                    (true, TFEND) => FEND,
+                    (true, TFESC) => FESC,
+                    _ => byte,
+                };
+                self.escaped = false;
+                self.buffer.push(byte);
+                None
+            }
+        }
+    }
+}
+
+// Payload of a data frame, or None for KISS control frames (TXDELAY, etc.)
+pub fn data_payload(frame: &[u8]) -> Option<&[u8]> {
+    match frame.split_first() {
+        Some((command, payload)) if command & 0x0F == CMD_DATA => Some(payload),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(decoder: &mut KissDecoder, bytes: &[u8]) -> Vec<Vec<u8>> {
+        bytes.iter().filter_map(|&byte| decoder.push(byte)).collect()
+    }
+
+    #[test]
+    fn escapes_fend_and_fesc() {
+        let encoded = encode_frame(&[1, FEND, 2, FESC, 3]);
+        assert_eq!(encoded, [FEND, CMD_DATA, 1, FESC, TFEND, 2, FESC, TFESC, 3, FEND]);
+    }
+
+    #[test]
+    fn round_trip_across_split_reads() {
+        let data = [FEND, FESC, 0x41, FESC, FEND, TFEND, TFESC];
+        let mut stream = encode_frame(&data);
+        stream.extend(encode_frame(b"second"));
+
+        // Every split point, including right after an FESC
+        for split in 0..stream.len() {
+            let mut decoder = KissDecoder::default();
+            let (first, rest) = stream.split_at(split);
+            let mut frames = decode(&mut decoder, first);
+            frames.extend(decode(&mut decoder, rest));
+
+            assert_eq!(frames.len(), 2, "split at {}", split);
+            assert_eq!(data_payload(&frames[0]), Some(&data[..]));
+            assert_eq!(data_payload(&frames[1]), Some(&b"second"[..]));
+        }
+    }
+
+    #[test]
+    fn skips_control_frames() {
+        let mut decoder = KissDecoder::default();
+        // TXDELAY command, then back-to-back FENDs between frames
+        let frames = decode(&mut decoder, &[FEND, 0x01, 30, FEND, FEND, FEND, CMD_DATA, 7, FEND]);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(data_payload(&frames[0]), None);
+        assert_eq!(data_payload(&frames[1]), Some(&[7u8][..]));
+    }
+}
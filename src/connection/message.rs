@@ -4,7 +4,57 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MessageType {
     Chat,
+    // Acknowledgement of a chat message
+    Ack,
+    // One part of a chat message too long for a single frame
+    ChatFragment,
     Position,
+    Presence,
+}
+
+// Presence announcements, carried as JSON in GameMessage content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Presence {
+    Hello {
+        callsign: String,
+        profile: Option<String>,
+    },
+    Leave {
+        callsign: String,
+    },
+    RosterQuery {
+        callsign: String,
+    },
+}
+
+// A chat message, carried as JSON in GameMessage content. Older clients and
+// long callsigns send no ID, and only messages with `ack` set are acknowledged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub callsign: String,
+    pub id: Option<u8>,
+    pub ack: bool,
+    pub text: String,
+}
+
+// `from` heard message `id` sent by `to`. Carried as JSON in GameMessage content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ack {
+    pub from: String,
+    pub to: String,
+    pub id: u8,
+}
+
+// Part `index` of chat message `id` from `callsign`, which has parts 0 to `last`.
+// Carried as JSON in GameMessage content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatFragment {
+    pub callsign: String,
+    pub id: u8,
+    pub index: u8,
+    pub last: u8,
+    pub ack: bool,
+    pub text: String,
 }
 
 // Game message structure for internal event passing
@@ -12,4 +62,6 @@ pub enum MessageType {
 pub struct GameMessage {
     pub content: String,
     pub message_type: MessageType,
+    // Station the frame is addressed to, None for everyone
+    pub destination: Option<String>,
 }
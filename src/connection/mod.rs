@@ -1,5 +1,14 @@
+pub mod airtime;
+pub mod beacon;
+pub mod callsign;
 pub mod compression;
+pub mod delivery;
+pub mod kiss;
 pub mod message;
+pub mod reassembly;
 pub mod resources;
+pub mod scheduler;
+pub mod sim;
 pub mod tnc_integration;
 pub mod tnc_plugin;
+pub mod transport;
@@ -0,0 +1,161 @@
+// Reassembly of chat messages that arrive in fragments
+//
+// Parts are collected per sender and message ID until all of them are in. A
+// message that stops arriving part way is given up REASSEMBLY_TIMEOUT after its
+// last part was heard, and shown with the missing parts marked.
+
+use super::message::ChatFragment;
+use bevy::prelude::*;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+const REASSEMBLY_TIMEOUT: Duration = Duration::from_secs(60);
+// Stands in for a part that never arrived
+const MISSING_PART: &str = "[...]";
+
+struct Partial {
+    parts: Vec<Option<String>>,
+    last_heard: Instant,
+    // Sent to us alone rather than to everyone
+    direct: bool,
+}
+
+impl Partial {
+    fn received(&self) -> usize {
+        self.parts.iter().filter(|part| part.is_some()).count()
+    }
+}
+
+// Messages still coming in, by sender and message ID
+#[derive(Resource, Default)]
+pub struct ChatReassembly {
+    partial: BTreeMap<(String, u8), Partial>,
+}
+
+impl ChatReassembly {
+    // Add a part of a message to everyone, or to us alone if `direct`; returns
+    // the chat line once the message is complete
+    pub fn add(&mut self, fragment: ChatFragment, direct: bool, now: Instant) -> Option<String> {
+        let count = fragment.last as usize + 1;
+        let key = (fragment.callsign, fragment.id);
+        let partial = self.partial.entry(key.clone()).or_insert_with(|| Partial {
+            parts: vec![None; count],
+            last_heard: now,
+            direct,
+        });
+        // The sender has moved on to a new message with the same ID
+        if partial.parts.len() != count {
+            partial.parts = vec![None; count];
+        }
+        partial.parts[fragment.index as usize] = Some(fragment.text);
+        partial.last_heard = now;
+        partial.direct = direct;
+
+        if partial.received() < count {
+            return None;
+        }
+        let partial = self.partial.remove(&key)?;
+        let text: String = partial.parts.into_iter().flatten().collect();
+        Some(format!("{}: {}", key.0, text))
+    }
+
+    // Give up on messages whose parts stopped coming, returning what did arrive
+    // as chat lines, with the sender for direct messages
+    pub fn expire(&mut self, now: Instant) -> Vec<(Option<String>, String)> {
+        let expired: Vec<(String, u8)> = self
+            .partial
+            .iter()
+            .filter(|(_, partial)| now.duration_since(partial.last_heard) >= REASSEMBLY_TIMEOUT)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        let mut lines = Vec::new();
+        for key in expired {
+            let Some(partial) = self.partial.remove(&key) else {
+                continue;
+            };
+            let received = partial.received();
+            let count = partial.parts.len();
+            let text: String = partial
+                .parts
+                .into_iter()
+                .map(|part| part.unwrap_or_else(|| MISSING_PART.to_string()))
+                .collect();
+            println!("[!] Gave up on message {} from {} with {}/{} parts", key.1, key.0, received, count);
+            let line = format!("{}: {} (incomplete, {}/{} parts)", key.0, text, received, count);
+            lines.push((partial.direct.then(|| key.0.clone()), line));
+        }
+        lines
+    }
+
+    // Messages still arriving: sender, parts received and parts in all
+    pub fn in_progress(&self) -> impl Iterator<Item = (&str, usize, usize)> {
+        self.partial
+            .iter()
+            .map(|((callsign, _), partial)| (callsign.as_str(), partial.received(), partial.parts.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fragment(callsign: &str, id: u8, index: u8, last: u8, text: &str) -> ChatFragment {
+        ChatFragment {
+            callsign: callsign.to_string(),
+            id,
+            index,
+            last,
+            ack: false,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn parts_in_any_order_make_the_message() {
+        let mut reassembly = ChatReassembly::default();
+        let now = Instant::now();
+        assert_eq!(reassembly.add(fragment("K1ABC", 1, 2, 2, "!"), false, now), None);
+        assert_eq!(reassembly.add(fragment("K1ABC", 1, 0, 2, "hello "), false, now), None);
+        // Another sender's message with the same ID is kept apart
+        assert_eq!(reassembly.add(fragment("N0CALL", 1, 0, 1, "other"), false, now), None);
+        assert_eq!(reassembly.in_progress().count(), 2);
+        let line = reassembly.add(fragment("K1ABC", 1, 1, 2, "world"), false, now);
+        assert_eq!(line.as_deref(), Some("K1ABC: hello world!"));
+        assert_eq!(reassembly.in_progress().collect::<Vec<_>>(), [("N0CALL", 1, 2)]);
+    }
+
+    #[test]
+    fn new_message_with_a_reused_id_starts_over() {
+        let mut reassembly = ChatReassembly::default();
+        let now = Instant::now();
+        reassembly.add(fragment("K1ABC", 7, 0, 2, "stale "), false, now);
+        assert_eq!(reassembly.add(fragment("K1ABC", 7, 0, 1, "fresh "), false, now), None);
+        let line = reassembly.add(fragment("K1ABC", 7, 1, 1, "start"), false, now);
+        assert_eq!(line.as_deref(), Some("K1ABC: fresh start"));
+    }
+
+    #[test]
+    fn stalled_messages_expire_with_missing_parts_marked() {
+        let mut reassembly = ChatReassembly::default();
+        let start = Instant::now();
+        reassembly.add(fragment("K1ABC", 3, 0, 2, "hello "), true, start);
+        reassembly.add(fragment("N0CALL", 4, 1, 1, "there"), false, start + REASSEMBLY_TIMEOUT / 2);
+
+        assert!(reassembly.expire(start + REASSEMBLY_TIMEOUT / 2).is_empty());
+        let expired = reassembly.expire(start + REASSEMBLY_TIMEOUT);
+        assert_eq!(
+            expired,
+            [(
+                Some("K1ABC".to_string()),
+                "K1ABC: hello [...][...] (incomplete, 1/3 parts)".to_string()
+            )]
+        );
+        let expired = reassembly.expire(start + REASSEMBLY_TIMEOUT * 2);
+        assert_eq!(
+            expired,
+            [(None, "N0CALL: [...]there (incomplete, 1/2 parts)".to_string())]
+        );
+        assert_eq!(reassembly.in_progress().count(), 0);
+    }
+}
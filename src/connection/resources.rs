@@ -1,5 +1,19 @@
+use super::transport::Transport;
 use bevy::prelude::*;
 
 // Position update interval in seconds
 #[derive(Resource)]
 pub struct PositionUpdateTime(pub u64);
+
+// Longest frame the modem takes (PACLEN), in bytes
+#[derive(Resource)]
+pub struct Paclen(pub usize);
+
+// Receive-only mode: nothing is ever transmitted and there is no local avatar
+#[derive(Resource)]
+pub struct ListenOnly(pub bool);
+
+// TNC connection opened and validated by the menu, handed to the TNC plugin
+// when the game starts so the modem only sees a single connect
+#[derive(Resource, Default)]
+pub struct TncConnection(pub Option<Box<dyn Transport>>);
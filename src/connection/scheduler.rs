@@ -0,0 +1,249 @@
+// Outgoing frame scheduler for the TNC sender
+//
+// Frames wait here until the channel is free instead of piling up in the modem,
+// where they would go out strictly first come, first served. Chat and presence
+// frames go out in the order they were queued, ahead of any position update, and
+// only the newest position is kept: an older one still waiting is worthless once
+// a newer one exists.
+//
+// `DutyCycle` keeps the station's transmissions within a share of the channel
+// time. Positions stop going out before the limit is reached, leaving the rest of
+// the budget for chat.
+
+use super::message::{GameMessage, MessageType};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Airtime usage is measured over this rolling window
+pub const DUTY_CYCLE_WINDOW: Duration = Duration::from_secs(600);
+// Share of the duty cycle budget position updates may use
+const POSITION_SHARE: f32 = 0.8;
+
+#[derive(Default)]
+pub struct OutgoingQueue {
+    // Chat and control frames, oldest first
+    urgent: VecDeque<GameMessage>,
+    // The latest position update not yet sent
+    position: Option<GameMessage>,
+}
+
+impl OutgoingQueue {
+    pub fn push(&mut self, message: GameMessage) {
+        match message.message_type {
+            MessageType::Position => {
+                if self.position.replace(message).is_some() {
+                    println!("[i] Replaced a queued position update with a newer one");
+                }
+            }
+            // Chat, ACKs and presence
+            _ => self.urgent.push_back(message),
+        }
+    }
+
+    // The next frame to send
+    pub fn pop(&mut self) -> Option<GameMessage> {
+        self.urgent.pop_front().or_else(|| self.position.take())
+    }
+
+    // Put a popped frame back to go out next. A position is only kept if no
+    // newer one has been queued meanwhile.
+    pub fn requeue(&mut self, message: GameMessage) {
+        match message.message_type {
+            MessageType::Position => {
+                if self.position.is_none() {
+                    self.position = Some(message);
+                }
+            }
+            // Chat, ACKs and presence
+            _ => self.urgent.push_front(message),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.urgent.len() + self.position.is_some() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Rolling record of our own transmissions
+pub struct DutyCycle {
+    // Maximum share of the window we may transmit (0.0 - 1.0)
+    max: f32,
+    // When each transmission ended, and how long it was
+    sent: VecDeque<(Instant, Duration)>,
+}
+
+impl DutyCycle {
+    pub fn new(max: f32) -> Self {
+        Self {
+            max: max.clamp(0.0, 1.0),
+            sent: VecDeque::new(),
+        }
+    }
+
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+
+    // Share of the last DUTY_CYCLE_WINDOW spent transmitting
+    pub fn usage(&self, now: Instant) -> f32 {
+        self.used(now).as_secs_f32() / DUTY_CYCLE_WINDOW.as_secs_f32()
+    }
+
+    fn used(&self, now: Instant) -> Duration {
+        self.in_window(now).map(|&(_, airtime)| airtime).sum()
+    }
+
+    fn in_window(&self, now: Instant) -> impl Iterator<Item = &(Instant, Duration)> {
+        self.sent
+            .iter()
+            .filter(move |&&(end, _)| now.saturating_duration_since(end) < DUTY_CYCLE_WINDOW)
+    }
+
+    // How long to hold a frame of `airtime` before it fits in the budget left
+    // for its kind of traffic. A frame always fits an otherwise idle window.
+    pub fn wait_for(&self, now: Instant, airtime: Duration, message_type: &MessageType) -> Duration {
+        if self.max >= 1.0 {
+            return Duration::ZERO;
+        }
+        let share = match message_type {
+            MessageType::Position => POSITION_SHARE,
+            _ => 1.0,
+        };
+        let budget = DUTY_CYCLE_WINDOW.mul_f32(self.max * share);
+
+        let mut used = self.used(now);
+        if used.is_zero() || used + airtime <= budget {
+            return Duration::ZERO;
+        }
+        // Find how many of the oldest transmissions have to age out first
+        for &(end, sent) in self.in_window(now) {
+            used = used.saturating_sub(sent);
+            if used.is_zero() || used + airtime <= budget {
+                return (end + DUTY_CYCLE_WINDOW).saturating_duration_since(now);
+            }
+        }
+        Duration::ZERO
+    }
+
+    // Note a transmission that has just been handed to the modem
+    pub fn record(&mut self, end: Instant, airtime: Duration) {
+        while self
+            .sent
+            .front()
+            .is_some_and(|&(sent_end, _)| end.saturating_duration_since(sent_end) >= DUTY_CYCLE_WINDOW)
+        {
+            self.sent.pop_front();
+        }
+        self.sent.push_back((end, airtime));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(message_type: MessageType, content: &str) -> GameMessage {
+        GameMessage {
+            content: content.to_string(),
+            message_type,
+            destination: None,
+        }
+    }
+
+    // Everything left in the queue, in the order it would go out
+    fn drain(queue: &mut OutgoingQueue) -> Vec<String> {
+        std::iter::from_fn(|| queue.pop()).map(|message| message.content).collect()
+    }
+
+    #[test]
+    fn chat_and_presence_go_before_positions_in_order() {
+        let mut queue = OutgoingQueue::default();
+        queue.push(message(MessageType::Position, "position"));
+        queue.push(message(MessageType::Chat, "first"));
+        queue.push(message(MessageType::Presence, "hello"));
+        queue.push(message(MessageType::Ack, "ack"));
+        assert_eq!(queue.len(), 4);
+        assert_eq!(drain(&mut queue), ["first", "hello", "ack", "position"]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn only_the_newest_position_is_kept() {
+        let mut queue = OutgoingQueue::default();
+        queue.push(message(MessageType::Position, "old"));
+        queue.push(message(MessageType::Position, "new"));
+        assert_eq!(queue.len(), 1);
+        assert_eq!(drain(&mut queue), ["new"]);
+    }
+
+    #[test]
+    fn requeued_frames_go_out_next() {
+        let mut queue = OutgoingQueue::default();
+        queue.push(message(MessageType::Chat, "first"));
+        queue.push(message(MessageType::Chat, "second"));
+        let first = queue.pop().unwrap();
+        queue.requeue(first);
+        assert_eq!(drain(&mut queue), ["first", "second"]);
+
+        // A requeued position comes back unless a newer one arrived meanwhile
+        queue.push(message(MessageType::Position, "old"));
+        let old = queue.pop().unwrap();
+        queue.requeue(old);
+        assert_eq!(drain(&mut queue), ["old"]);
+
+        queue.push(message(MessageType::Position, "old"));
+        let old = queue.pop().unwrap();
+        queue.push(message(MessageType::Position, "new"));
+        queue.requeue(old);
+        assert_eq!(drain(&mut queue), ["new"]);
+    }
+
+    #[test]
+    fn no_limit_never_holds_frames() {
+        let mut duty_cycle = DutyCycle::new(1.0);
+        let now = Instant::now();
+        duty_cycle.record(now, DUTY_CYCLE_WINDOW);
+        let airtime = Duration::from_secs(10);
+        assert_eq!(duty_cycle.wait_for(now, airtime, &MessageType::Position), Duration::ZERO);
+    }
+
+    #[test]
+    fn frames_wait_for_the_budget() {
+        // 10% of 600 seconds
+        let mut duty_cycle = DutyCycle::new(0.1);
+        let now = Instant::now();
+        // Even a frame longer than the budget goes out on an idle channel
+        let long = Duration::from_secs(90);
+        assert_eq!(duty_cycle.wait_for(now, long, &MessageType::Chat), Duration::ZERO);
+
+        duty_cycle.record(now, Duration::from_secs(50));
+        assert!((duty_cycle.usage(now) - 50.0 / 600.0).abs() < 1e-6);
+        let chat = |secs| duty_cycle.wait_for(now, Duration::from_secs(secs), &MessageType::Chat);
+        assert_eq!(chat(10), Duration::ZERO);
+        // Held until the earlier transmission leaves the window
+        assert_eq!(chat(11), DUTY_CYCLE_WINDOW);
+
+        let later = now + DUTY_CYCLE_WINDOW;
+        assert_eq!(duty_cycle.usage(later), 0.0);
+        let airtime = Duration::from_secs(11);
+        assert_eq!(duty_cycle.wait_for(later, airtime, &MessageType::Chat), Duration::ZERO);
+    }
+
+    #[test]
+    fn positions_leave_room_for_chat() {
+        // Positions may use 80% of the 60 second budget
+        let mut duty_cycle = DutyCycle::new(0.1);
+        let now = Instant::now();
+        duty_cycle.record(now, Duration::from_secs(40));
+        let wait = |secs, message_type| {
+            duty_cycle.wait_for(now, Duration::from_secs(secs), &message_type)
+        };
+        assert_eq!(wait(8, MessageType::Position), Duration::ZERO);
+        assert_eq!(wait(9, MessageType::Position), DUTY_CYCLE_WINDOW);
+        assert_eq!(wait(20, MessageType::Chat), Duration::ZERO);
+    }
+}
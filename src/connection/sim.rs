@@ -0,0 +1,317 @@
+// Simulated half-duplex radio channel for testing and demos
+//
+// Every transport opened on the same channel name in this process shares one
+// frequency. Frames take real time on air (TXDELAY plus bits at the bit rate),
+// a station cannot hear while it is transmitting, overlapping transmissions
+// collide and are lost for everyone, and surviving frames are dropped at random
+// per receiver with the configured loss rate.
+//
+// Channels are not shared between processes, so a game client on its own only
+// sees the airtime and pacing; several clients on one machine meet through
+// hamrpg-hub instead. The tests below put several stations on one channel.
+
+use super::airtime::frame_airtime;
+use super::transport::{IncomingFrame, Transport};
+use ax25::frame::Ax25Frame;
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use rand::{Rng, rng};
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex, OnceLock, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug)]
+pub struct SimConfig {
+    pub bit_rate: u32,
+    pub txdelay_ms: u32,
+    // Probability (0.0 - 1.0) that a receiver misses an otherwise clean frame
+    pub loss: f32,
+}
+
+impl SimConfig {
+    // Parse "BITRATE:CHANNEL[:TXDELAY_MS[:LOSS_PCT]]" into the channel name and config
+    pub fn parse(spec: &str) -> Result<(String, SimConfig), String> {
+        let mut parts = spec.split(':');
+        let bit_rate = parts
+            .next()
+            .and_then(|b| b.parse::<u32>().ok())
+            .filter(|&b| b > 0)
+            .ok_or_else(|| format!("Invalid simulated bit rate in: {}", spec))?;
+        let channel = parts.next().filter(|c| !c.is_empty()).unwrap_or("local").to_string();
+        let txdelay_ms = match parts.next() {
+            Some(t) => t.parse().map_err(|_| format!("Invalid TXDELAY: {}", t))?,
+            None => 300,
+        };
+        let loss_pct: f32 = match parts.next() {
+            Some(l) => l.parse().map_err(|_| format!("Invalid loss percentage: {}", l))?,
+            None => 0.0,
+        };
+
+        Ok((
+            channel,
+            SimConfig {
+                bit_rate,
+                txdelay_ms,
+                loss: (loss_pct / 100.0).clamp(0.0, 1.0),
+            },
+        ))
+    }
+}
+
+struct Transmission {
+    station: usize,
+    start: Instant,
+    end: Instant,
+    frame: Ax25Frame,
+    collided: bool,
+}
+
+#[derive(Default)]
+struct ChannelState {
+    stations: HashMap<usize, Sender<IncomingFrame>>,
+    next_station: usize,
+    // When each station's transmitter is next free
+    busy_until: HashMap<usize, Instant>,
+    on_air: Vec<Transmission>,
+}
+
+pub struct SimChannel {
+    config: SimConfig,
+    state: Mutex<ChannelState>,
+    wakeup: Condvar,
+}
+
+// Channels shared by name within this process
+static CHANNELS: OnceLock<Mutex<HashMap<String, Weak<SimChannel>>>> = OnceLock::new();
+
+impl SimChannel {
+    // Join the named channel, creating it with `config` if nobody is on it yet
+    pub fn join(name: &str, config: SimConfig) -> Arc<SimChannel> {
+        let mut channels = CHANNELS.get_or_init(Default::default).lock().unwrap();
+        if let Some(channel) = channels.get(name).and_then(Weak::upgrade) {
+            return channel;
+        }
+
+        let channel = Arc::new(SimChannel {
+            config,
+            state: Mutex::new(ChannelState::default()),
+            wakeup: Condvar::new(),
+        });
+        channels.insert(name.to_string(), Arc::downgrade(&channel));
+
+        let weak = Arc::downgrade(&channel);
+        thread::spawn(move || run_channel(weak));
+        channel
+    }
+}
+
+// Deliver transmissions as they finish; exits once every station has left
+fn run_channel(channel: Weak<SimChannel>) {
+    loop {
+        let Some(channel) = channel.upgrade() else {
+            return;
+        };
+        let mut state = channel.state.lock().unwrap();
+
+        let now = Instant::now();
+        let (done, pending): (Vec<_>, Vec<_>) =
+            state.on_air.drain(..).partition(|t| t.end <= now);
+        state.on_air = pending;
+
+        let mut rng = rng();
+        for transmission in done {
+            if transmission.collided {
+                println!("[i] Simulated channel: collision, frame lost");
+                continue;
+            }
+            for (&station, sender) in &state.stations {
+                if station == transmission.station {
+                    continue;
+                }
+                // Half duplex: a station keyed up during the frame cannot hear it
+                let deaf = state
+                    .on_air
+                    .iter()
+                    .any(|t| t.station == station && t.start < transmission.end);
+                if deaf || rng.random::<f32>() < channel.config.loss {
+                    continue;
+                }
+                let _ = sender.send(Ok(transmission.frame.clone()));
+            }
+        }
+
+        let timeout = state
+            .on_air
+            .iter()
+            .map(|t| t.end.saturating_duration_since(now))
+            .min()
+            .unwrap_or(Duration::from_millis(500));
+        drop(channel.wakeup.wait_timeout(state, timeout).unwrap());
+    }
+}
+
+// One station on a simulated channel
+pub struct SimTransport {
+    channel: Arc<SimChannel>,
+    station: usize,
+    receiver: Receiver<IncomingFrame>,
+}
+
+impl SimTransport {
+    pub fn open(spec: &str) -> Result<Self, String> {
+        let (name, config) = SimConfig::parse(spec)?;
+        let channel = SimChannel::join(&name, config);
+
+        let (tx, rx) = unbounded();
+        let station = {
+            let mut state = channel.state.lock().unwrap();
+            let station = state.next_station;
+            state.next_station += 1;
+            state.stations.insert(station, tx);
+            station
+        };
+        println!(
+            "[i] Joined simulated channel '{}' as station {} ({} bps, TXDELAY {} ms, {:.0}% loss)",
+            name,
+            station,
+            channel.config.bit_rate,
+            channel.config.txdelay_ms,
+            channel.config.loss * 100.0
+        );
+
+        Ok(Self {
+            channel,
+            station,
+            receiver: rx,
+        })
+    }
+}
+
+impl Transport for SimTransport {
+    fn send_frame(&self, frame: &Ax25Frame) -> Result<(), String> {
+        let config = self.channel.config;
+        let airtime = frame_airtime(frame.to_bytes().len(), config.bit_rate, config.txdelay_ms);
+
+        let mut state = self.channel.state.lock().unwrap();
+        // Frames from one station go out back to back, like a TNC's transmit queue
+        let now = Instant::now();
+        let start = state
+            .busy_until
+            .get(&self.station)
+            .copied()
+            .filter(|&busy| busy > now)
+            .unwrap_or(now);
+        let end = start + airtime;
+        state.busy_until.insert(self.station, end);
+
+        let mut collided = false;
+        for other in state.on_air.iter_mut() {
+            if other.station != self.station && other.start < end && start < other.end {
+                other.collided = true;
+                collided = true;
+            }
+        }
+        state.on_air.push(Transmission {
+            station: self.station,
+            start,
+            end,
+            frame: frame.clone(),
+            collided,
+        });
+        self.channel.wakeup.notify_all();
+        Ok(())
+    }
+
+    fn incoming(&self) -> Receiver<IncomingFrame> {
+        self.receiver.clone()
+    }
+}
+
+impl Drop for SimTransport {
+    fn drop(&mut self) {
+        let mut state = self.channel.state.lock().unwrap();
+        state.stations.remove(&self.station);
+        state.busy_until.remove(&self.station);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ax25::frame::{CommandResponse, FrameContent, ProtocolIdentifier, UnnumberedInformation};
+
+    fn frame(source: &str, info: &str) -> Ax25Frame {
+        Ax25Frame {
+            source: source.parse().unwrap(),
+            destination: "HAMRPG-0".parse().unwrap(),
+            route: Vec::new(),
+            command_or_response: Some(CommandResponse::Command),
+            content: FrameContent::UnnumberedInformation(UnnumberedInformation {
+                pid: ProtocolIdentifier::None,
+                info: info.as_bytes().to_vec(),
+                poll_or_final: false,
+            }),
+        }
+    }
+
+    // Next frame a station hears, as sent on air, since frames don't compare
+    fn hear(station: &SimTransport) -> Vec<u8> {
+        station.incoming().recv_timeout(SETTLE).unwrap().unwrap().to_bytes()
+    }
+
+    // Long enough for any test frame to finish at 9600 bps without TXDELAY
+    const SETTLE: Duration = Duration::from_millis(300);
+
+    #[test]
+    fn clean_frame_reaches_every_other_station() {
+        let a = SimTransport::open("9600:clean:0").unwrap();
+        let b = SimTransport::open("9600:clean:0").unwrap();
+        let c = SimTransport::open("9600:clean:0").unwrap();
+
+        let sent = frame("AAA-1", "hello");
+        a.send_frame(&sent).unwrap();
+        assert_eq!(hear(&b), sent.to_bytes());
+        assert_eq!(hear(&c), sent.to_bytes());
+        // No echo of our own transmission
+        assert!(a.incoming().recv_timeout(SETTLE).is_err());
+    }
+
+    #[test]
+    fn overlapping_frames_collide() {
+        let a = SimTransport::open("9600:collide:0").unwrap();
+        let b = SimTransport::open("9600:collide:0").unwrap();
+        let c = SimTransport::open("9600:collide:0").unwrap();
+
+        a.send_frame(&frame("AAA-1", "one")).unwrap();
+        b.send_frame(&frame("BBB-1", "two")).unwrap();
+        for station in [&a, &b, &c] {
+            assert!(station.incoming().recv_timeout(SETTLE).is_err());
+        }
+
+        // The channel is usable again once both are off the air
+        let sent = frame("AAA-1", "three");
+        a.send_frame(&sent).unwrap();
+        assert_eq!(hear(&c), sent.to_bytes());
+    }
+
+    #[test]
+    fn frames_from_one_station_queue_instead_of_colliding() {
+        let a = SimTransport::open("9600:queue:0").unwrap();
+        let b = SimTransport::open("9600:queue:0").unwrap();
+
+        let (first, second) = (frame("AAA-1", "first"), frame("AAA-1", "second"));
+        a.send_frame(&first).unwrap();
+        a.send_frame(&second).unwrap();
+        assert_eq!(hear(&b), first.to_bytes());
+        assert_eq!(hear(&b), second.to_bytes());
+    }
+
+    #[test]
+    fn lossy_channel_drops_frames() {
+        let a = SimTransport::open("9600:lossy:0:100").unwrap();
+        let b = SimTransport::open("9600:lossy:0:100").unwrap();
+
+        a.send_frame(&frame("AAA-1", "lost")).unwrap();
+        assert!(b.incoming().recv_timeout(SETTLE).is_err());
+    }
+}
@@ -1,32 +1,80 @@
 // Game-level TNC event handlers
 // Processes incoming radio packets and sends position updates
 
-use super::compression::encode_position;
-use super::resources::PositionUpdateTime;
-use crate::components::{Entity, RemotePlayer};
-use crate::connection::message::MessageType;
-use crate::connection::tnc_plugin::{TncIncomingEvent, TncOutgoingEvent};
-use crate::systems::remote_player::{PlayerPositionData, spawn_player_remote};
+use super::compression::{encode_hello, encode_leave, encode_position, encode_roster_query};
+use super::beacon::BeaconSchedule;
+use super::callsign::{normalize_callsign, same_station};
+use super::delivery::ChatDelivery;
+use super::reassembly::ChatReassembly;
+use super::resources::{ListenOnly, PositionUpdateTime};
+use crate::config::Settings;
+use crate::components::{AnimationIndices, Animations, Entity, RemotePlayer};
+use crate::connection::message::{Ack, ChatFragment, ChatMessage, MessageType, Presence};
+use crate::connection::tnc_plugin::{TncIncomingEvent, TncOutgoingEvent, stop_tnc_connection};
+use crate::systems::remote_player::{
+    PlayerPositionData, retarget_remote_player, spawn_player_remote,
+};
+use crate::systems::world_map::{WorldMap, zone_name};
+use avian2d::prelude::LinearVelocity;
 use bevy::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use rand::{rng, Rng};
 use std::time::{Duration, Instant};
 
+// Stations answer a roster query after a random delay in this range (seconds)
+// so their replies don't all key up at once
+const ROSTER_REPLY_DELAY: std::ops::RangeInclusive<f32> = 1.0..=12.0;
+
 // Process incoming TNC messages
+#[allow(clippy::too_many_arguments)]
 pub fn handle_tnc_events(
     mut commands: Commands,
     mut incoming_events: EventReader<TncIncomingEvent>,
     mut game_state: ResMut<GameState>,
     asset_server: Res<AssetServer>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-    mut transforms: Query<(&Transform, &mut RemotePlayer)>,
+    mut transforms: Query<(&Transform, &mut RemotePlayer, &mut AnimationIndices, &Animations)>,
+    mut roster_reply: ResMut<PendingRosterReply>,
+    world_map: Res<WorldMap>,
+    mut beacon_schedule: ResMut<BeaconSchedule>,
+    pos_update_time: Res<PositionUpdateTime>,
+    mut reassembly: ResMut<ChatReassembly>,
+    mut delivery: ResMut<ChatDelivery>,
 ) {
     for event in incoming_events.read() {
         match event.message_type {
             MessageType::Position => {
                 if let Ok(pos_data) = serde_json::from_str::<PlayerPositionData>(&event.message) {
                     // Don't process our own position updates
-                    if pos_data.callsign == game_state.player_callsign {
+                    if same_station(&pos_data.callsign, &game_state.player_callsign) {
+                        continue;
+                    }
+                    game_state
+                        .last_heard
+                        .insert(pos_data.callsign.clone(), Instant::now());
+                    game_state
+                        .last_fixes
+                        .insert(pos_data.callsign.clone(), pos_data.clone());
+                    beacon_schedule.heard(
+                        &pos_data.callsign,
+                        pos_data.slot,
+                        &game_state.player_callsign,
+                        pos_update_time.0,
+                    );
+                    if !game_state.known_players.contains(&pos_data.callsign) {
+                        game_state.known_players.push(pos_data.callsign.clone());
+                    }
+
+                    // Players on other maps are only listed in the roster
+                    if pos_data.zone != world_map.zone {
+                        if let Some(entity) = game_state.player_entities.remove(&pos_data.callsign) {
+                            commands.entity(entity).despawn();
+                            println!(
+                                "[i] {} went to {}",
+                                pos_data.callsign,
+                                zone_name(pos_data.zone)
+                            );
+                        }
                         continue;
                     }
 
@@ -36,10 +84,10 @@ pub fn handle_tnc_events(
                             &mut commands,
                             &asset_server,
                             &mut texture_atlas_layouts,
+                            &world_map,
                             &pos_data,
                         );
 
-                        game_state.known_players.push(pos_data.callsign.clone());
                         game_state
                             .player_entities
                             .insert(pos_data.callsign.clone(), entity);
@@ -48,18 +96,29 @@ pub fn handle_tnc_events(
                     } else {
                         // Update existing player's position with smooth movement
                         if let Some(&entity) = game_state.player_entities.get(&pos_data.callsign) {
-                            if let Ok((transform, mut remote_player)) = transforms.get_mut(entity) {
+                            if let Ok((transform, mut remote_player, mut animation_indices, animations)) =
+                                transforms.get_mut(entity)
+                            {
                                 let new_position =
                                     Vec3::new(pos_data.x, pos_data.y, transform.translation.z);
+                                remote_player.facing = pos_data.direction.clone();
+                                remote_player.reported_moving = pos_data.moving;
+                                remote_player.last_update = Instant::now();
+                                remote_player.velocity = Vec2::new(pos_data.vx, pos_data.vy);
 
-                                // Only start movement if the position actually changed
-                                if transform.translation.distance(new_position) > 1.0 {
-                                    remote_player.start_position = transform.translation;
-                                    remote_player.target_position = new_position;
-                                    remote_player.move_start_time = Instant::now();
-                                    remote_player.move_duration = 2.0; // 2 seconds to move
-                                    remote_player.is_moving = true;
-                                    remote_player.last_update = Instant::now();
+                                // Only start movement if the position changed or the
+                                // avatar is (or should keep) moving; a running
+                                // extrapolation is always re-based on the new fix
+                                if transform.translation.distance(new_position) > 1.0
+                                    || remote_player.velocity != Vec2::ZERO
+                                    || remote_player.is_moving
+                                {
+                                    retarget_remote_player(
+                                        &mut remote_player,
+                                        transform.translation,
+                                        new_position,
+                                        &world_map,
+                                    );
 
                                     println!(
                                         "[i] Updating position for {}: {:?} -> {:?}",
@@ -67,6 +126,10 @@ pub fn handle_tnc_events(
                                         remote_player.start_position,
                                         new_position
                                     );
+                                } else {
+                                    // Turned or stopped in place
+                                    *animation_indices =
+                                        animations.for_state(&pos_data.direction, pos_data.moving);
                                 }
                             } else {
                                 println!(
@@ -80,37 +143,297 @@ pub fn handle_tnc_events(
                 }
             }
             MessageType::Chat => {
-                println!("[i] Chat message received: {}", event.message);
-
-                // Add the message to our chat history
-                game_state.chat_messages.push(event.message.clone());
-
-                // Limit chat history to prevent excessive memory usage
-                if game_state.chat_messages.len() > 100 {
-                    game_state.chat_messages.remove(0);
+                let Ok(mut chat) = serde_json::from_str::<ChatMessage>(&event.message) else {
+                    continue;
+                };
+                // Conversations are keyed by the normalized callsign
+                chat.callsign = normalize_callsign(&chat.callsign);
+                if same_station(&chat.callsign, &game_state.player_callsign) {
+                    continue;
+                }
+                // Direct messages to other stations are neither shown nor acknowledged
+                let thread = match &event.destination {
+                    None => None,
+                    Some(to) if same_station(to, &game_state.player_callsign) => {
+                        Some(chat.callsign.clone())
+                    }
+                    Some(_) => continue,
+                };
+                let line = format!("{}: {}", chat.callsign, chat.text);
+                if let Some(id) = chat.id {
+                    if chat.ack {
+                        delivery.queue_ack(&chat.callsign, id);
+                    }
+                    if delivery.seen_before(&chat.callsign, id, &chat.text) {
+                        println!("[i] Repeat of message {} from {}", id, chat.callsign);
+                        continue;
+                    }
+                }
+                println!("[i] Chat message received: {}", line);
+                add_chat_line(&mut game_state, thread.as_deref(), line);
+            }
+            MessageType::ChatFragment => {
+                let Ok(mut fragment) = serde_json::from_str::<ChatFragment>(&event.message) else {
+                    continue;
+                };
+                fragment.callsign = normalize_callsign(&fragment.callsign);
+                if same_station(&fragment.callsign, &game_state.player_callsign) {
+                    continue;
+                }
+                let direct = match &event.destination {
+                    None => false,
+                    Some(to) if same_station(to, &game_state.player_callsign) => true,
+                    Some(_) => continue,
+                };
+                println!(
+                    "[i] Chat part {}/{} of message {} from {}",
+                    fragment.index as u32 + 1,
+                    fragment.last as u32 + 1,
+                    fragment.id,
+                    fragment.callsign
+                );
+                let (callsign, id, ack) = (fragment.callsign.clone(), fragment.id, fragment.ack);
+                // A retry of a message we already put together: acknowledge it
+                // again once, on its last part
+                if delivery.shown_recently(&callsign, id) {
+                    if ack && fragment.index == fragment.last {
+                        delivery.queue_ack(&callsign, id);
+                    }
+                    continue;
+                }
+                if let Some(line) = reassembly.add(fragment, direct, Instant::now()) {
+                    if ack {
+                        delivery.queue_ack(&callsign, id);
+                    }
+                    delivery.seen_before(&callsign, id, &line);
+                    println!("[i] Chat message received: {}", line);
+                    let thread = direct.then_some(callsign.as_str());
+                    add_chat_line(&mut game_state, thread, line);
+                }
+            }
+            MessageType::Ack => {
+                let Ok(ack) = serde_json::from_str::<Ack>(&event.message) else {
+                    continue;
+                };
+                if same_station(&ack.to, &game_state.player_callsign) {
+                    delivery.acknowledged(ack.id, &ack.from, &mut game_state);
+                }
+            }
+            MessageType::Presence => {
+                let Ok(presence) = serde_json::from_str::<Presence>(&event.message) else {
+                    continue;
+                };
+                match presence {
+                    Presence::Hello { callsign, profile } => {
+                        if same_station(&callsign, &game_state.player_callsign) {
+                            continue;
+                        }
+                        game_state.last_heard.insert(callsign.clone(), Instant::now());
+                        if !game_state.known_players.contains(&callsign) {
+                            println!("[i] Station on frequency: {}", callsign);
+                            let notice = match profile {
+                                Some(profile) => format!("* {} is here ({})", callsign, profile),
+                                None => format!("* {} is here", callsign),
+                            };
+                            game_state.chat_messages.push(notice);
+                            game_state.known_players.push(callsign);
+                        }
+                    }
+                    Presence::Leave { callsign } => {
+                        if same_station(&callsign, &game_state.player_callsign) {
+                            continue;
+                        }
+                        println!("[i] Station left: {}", callsign);
+                        if let Some(entity) = game_state.player_entities.remove(&callsign) {
+                            commands.entity(entity).despawn();
+                        }
+                        game_state.known_players.retain(|p| p != &callsign);
+                        game_state.last_heard.remove(&callsign);
+                        game_state.last_fixes.remove(&callsign);
+                        game_state.chat_messages.push(format!("* {} left", callsign));
+                    }
+                    Presence::RosterQuery { callsign } => {
+                        if same_station(&callsign, &game_state.player_callsign) {
+                            continue;
+                        }
+                        // Answer once per burst of queries, after a random delay
+                        if roster_reply.0.is_none() {
+                            let delay = rng().random_range(ROSTER_REPLY_DELAY);
+                            roster_reply.0 =
+                                Some(Instant::now() + Duration::from_secs_f32(delay));
+                        }
+                    }
                 }
             }
         }
     }
+
+    // Show what arrived of messages that stopped coming in
+    for (thread, line) in reassembly.expire(Instant::now()) {
+        add_chat_line(&mut game_state, thread.as_deref(), line);
+    }
+}
+
+// Add a line to the main chat (thread None) or to the direct messages with a
+// station, counting it as unread there
+pub fn add_chat_line(game_state: &mut GameState, thread: Option<&str>, line: String) {
+    if let Some(callsign) = thread {
+        *game_state.unread.entry(callsign.to_string()).or_default() += 1;
+    }
+    let lines = game_state.chat_lines_mut(thread);
+    lines.push(line);
+
+    // Limit chat history to prevent excessive memory usage
+    if lines.len() > 100 {
+        lines.remove(0);
+    }
+}
+
+// Announce ourselves and ask who else is on frequency
+pub fn announce_presence(
+    mut event_writer: EventWriter<TncOutgoingEvent>,
+    game_state: Res<GameState>,
+    listen_only: Res<ListenOnly>,
+) {
+    if listen_only.0 {
+        return;
+    }
+
+    let hello = encode_hello(&game_state.player_callsign, game_state.player_profile.as_deref());
+    let query = encode_roster_query(&game_state.player_callsign);
+    for message in [hello, query].into_iter().flatten() {
+        event_writer.write(TncOutgoingEvent {
+            message,
+            message_type: MessageType::Presence,
+            destination: None,
+        });
+    }
+}
+
+// Reply to a roster query with a hello and our position once the random delay is up
+pub fn answer_roster_queries(
+    query: Query<(&Transform, &Animations, &AnimationIndices, &LinearVelocity), With<Entity>>,
+    mut event_writer: EventWriter<TncOutgoingEvent>,
+    mut roster_reply: ResMut<PendingRosterReply>,
+    game_state: Res<GameState>,
+    listen_only: Res<ListenOnly>,
+    world_map: Res<WorldMap>,
+) {
+    let Some(due) = roster_reply.0 else {
+        return;
+    };
+    if Instant::now() < due {
+        return;
+    }
+    roster_reply.0 = None;
+
+    if listen_only.0 {
+        return;
+    }
+
+    if let Some(hello) =
+        encode_hello(&game_state.player_callsign, game_state.player_profile.as_deref())
+    {
+        event_writer.write(TncOutgoingEvent {
+            message: hello,
+            message_type: MessageType::Presence,
+            destination: None,
+        });
+    }
+    for (transform, animations, current_animation, velocity) in query.iter() {
+        event_writer.write(TncOutgoingEvent {
+            message: encode_position(
+                &game_state.player_callsign,
+                transform.translation.x,
+                transform.translation.y,
+                animations.facing(current_animation),
+                velocity.0 != Vec2::ZERO,
+                (velocity.0.x, velocity.0.y),
+                world_map.zone,
+                None,
+            ),
+            message_type: MessageType::Position,
+            destination: None,
+        });
+    }
+    println!("[i] Answered roster query");
+}
+
+// Say goodbye on a clean exit so others drop us right away instead of timing out.
+// Runs in Last, after the frame's AppExit was written. The leave goes out with
+// everything else still queued when the TNC connection is flushed and closed.
+pub fn send_leave_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    mut event_writer: EventWriter<TncOutgoingEvent>,
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    listen_only: Res<ListenOnly>,
+) {
+    if exit_events.read().next().is_none() {
+        return;
+    }
+    if let (false, Some(leave)) = (listen_only.0, encode_leave(&game_state.player_callsign)) {
+        println!("[i] Sending leave packet");
+        event_writer.write(TncOutgoingEvent {
+            message: leave,
+            message_type: MessageType::Presence,
+            destination: None,
+        });
+    }
+    commands.queue(stop_tnc_connection);
+}
+
+// Say goodbye when going back to the menu. Runs on leaving the game, before
+// the TNC connection is flushed and closed.
+pub fn send_leave_on_quit(
+    mut event_writer: EventWriter<TncOutgoingEvent>,
+    game_state: Res<GameState>,
+    listen_only: Res<ListenOnly>,
+) {
+    if listen_only.0 {
+        return;
+    }
+    if let Some(leave) = encode_leave(&game_state.player_callsign) {
+        println!("[i] Sending leave packet");
+        event_writer.write(TncOutgoingEvent {
+            message: leave,
+            message_type: MessageType::Presence,
+            destination: None,
+        });
+    }
 }
 
 // Send player position updates via TNC
+#[allow(clippy::too_many_arguments)]
 pub fn send_position_updates(
-    query: Query<(&Transform, &Entity)>,
+    query: Query<(&Transform, &Animations, &AnimationIndices, &LinearVelocity), With<Entity>>,
     mut event_writer: EventWriter<TncOutgoingEvent>,
-    mut last_update: Local<Option<Instant>>,
+    // When the last update went out, and the jitter drawn for the next one
+    mut last_update: Local<Option<(Instant, i64)>>,
     game_state: Res<GameState>,
     pos_update_time: Res<PositionUpdateTime>,
+    listen_only: Res<ListenOnly>,
+    world_map: Res<WorldMap>,
+    settings: Res<Settings>,
+    mut beacon_schedule: ResMut<BeaconSchedule>,
 ) {
-    // Only send position updates at intervals with random timing
+    // Listeners never beacon
+    if listen_only.0 {
+        return;
+    }
+
+    // Send in our time slot, or at intervals with random timing
     let now = Instant::now();
-    let should_update = if let Some(last) = *last_update {
-        let mut rng = rng();
-        let random_offset = rng.random_range(-4..=4);
-        let randomized_interval = (pos_update_time.0 as i64 + random_offset).max(1);
-        now.duration_since(last) >= Duration::from_secs(randomized_interval.try_into().unwrap())
-    } else {
-        true // First update should happen immediately
+    let slotted = settings.slotted_beacons && last_update.is_some();
+    let should_update = match *last_update {
+        None => true, // First update should happen immediately
+        Some(_) if settings.slotted_beacons => {
+            beacon_schedule.due(&game_state.player_callsign, pos_update_time.0)
+        }
+        Some((last, random_offset)) => {
+            let randomized_interval = (pos_update_time.0 as i64 + random_offset).max(1);
+            now.duration_since(last) >= Duration::from_secs(randomized_interval as u64)
+        }
     };
 
     // Skip if not time to update yet
@@ -118,22 +441,32 @@ pub fn send_position_updates(
         return;
     }
 
-    // Update the last update time
-    *last_update = Some(now);
+    // Update the last update time, and draw the jitter for the next update once
+    // so it isn't redrawn every frame
+    *last_update = Some((now, rng().random_range(-4..=4)));
+
+    // Tell the others which slot this went out in, so a late frame isn't
+    // mistaken for one sent in a later slot
+    let slot = slotted.then(|| beacon_schedule.slot()).flatten();
 
     // Get player position and send update
-    for (transform, _) in query.iter() {
-        // Encode position using custom compact protocol
+    for (transform, animations, current_animation, velocity) in query.iter() {
+        // Encode position, facing and walking state using custom compact protocol
         let encoded = encode_position(
             &game_state.player_callsign,
             transform.translation.x,
             transform.translation.y,
-            &get_player_direction(),
+            animations.facing(current_animation),
+            velocity.0 != Vec2::ZERO,
+            (velocity.0.x, velocity.0.y),
+            world_map.zone,
+            slot,
         );
 
         event_writer.write(TncOutgoingEvent {
             message: encoded,
             message_type: MessageType::Position,
+            destination: None,
         });
         println!(
             "[i] Position update sent for {}",
@@ -142,16 +475,43 @@ pub fn send_position_updates(
     }
 }
 
-// Helper function to determine player direction
-fn get_player_direction() -> String {
-    "south".to_string() // Replace with actual direction logic
-}
-
 // Game state resource
-#[derive(Resource)]
+#[derive(Resource, Default)]
 pub struct GameState {
     pub chat_messages: Vec<String>,
     pub known_players: Vec<String>,
     pub player_entities: HashMap<String, bevy::prelude::Entity>,
     pub player_callsign: String,
+    pub player_profile: Option<String>,
+    // When each known station was last heard, for stations with no avatar yet
+    pub last_heard: HashMap<String, Instant>,
+    // Last position report from each station, including which zone it is in
+    pub last_fixes: HashMap<String, PlayerPositionData>,
+    // Direct message conversations by the other station's callsign
+    pub direct_messages: BTreeMap<String, Vec<String>>,
+    // Direct messages not yet looked at, by conversation
+    pub unread: HashMap<String, usize>,
 }
+
+impl GameState {
+    // Lines of the main chat (None) or of the conversation with a station
+    pub fn chat_lines(&self, thread: Option<&str>) -> &[String] {
+        match thread {
+            None => &self.chat_messages,
+            Some(callsign) => self.direct_messages.get(callsign).map_or(&[], Vec::as_slice),
+        }
+    }
+
+    // Lines of the main chat (None) or of the conversation with a station,
+    // which is started if there is none yet
+    pub fn chat_lines_mut(&mut self, thread: Option<&str>) -> &mut Vec<String> {
+        match thread {
+            None => &mut self.chat_messages,
+            Some(callsign) => self.direct_messages.entry(callsign.to_string()).or_default(),
+        }
+    }
+}
+
+// When to answer the last roster query, if an answer is due
+#[derive(Resource, Default)]
+pub struct PendingRosterReply(pub Option<Instant>);
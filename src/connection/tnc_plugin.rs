@@ -1,36 +1,119 @@
 // TNC (Terminal Node Controller) integration plugin
 // Manages connection to KISS-compatible TNC software for AX.25 packet transmission
 
+use super::airtime::frame_airtime;
+use super::callsign::parse_callsign;
 use super::compression::decode_packet;
-use super::message::{GameMessage, MessageType};
+use super::message::{GameMessage, MessageType, Presence};
+use super::resources::{ListenOnly, TncConnection};
+use super::scheduler::{DutyCycle, OutgoingQueue};
+use super::sim::SimConfig;
+use super::transport::{Transport, open_transport};
 use crate::menu::{AppState, MenuConfig};
 use ax25::frame::{
     Address, Ax25Frame, CommandResponse, FrameContent, ProtocolIdentifier, UnnumberedInformation,
 };
-use ax25_tnc::tnc::{Tnc, TncAddress};
 use bevy::prelude::*;
-use crossbeam_channel::{Receiver, Sender, unbounded};
+use crossbeam_channel::{Receiver, Sender, TryRecvError, select, unbounded};
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread;
+use std::time::{Duration, Instant};
+
+// Reconnect backoff: 1s, 2s, 4s ... capped, giving up after a number of attempts
+const RECONNECT_BASE_DELAY: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+const MAX_RECONNECT_ATTEMPTS: u32 = 8;
+
+// How long leaving the game waits for queued frames to go out and the TNC
+// threads to stop
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
 
 // TNC communication events
+// `destination` is the station a frame is addressed to, or None for frames to
+// every player
 #[derive(Event)]
 pub struct TncIncomingEvent {
     pub message: String,
     pub message_type: MessageType,
+    pub destination: Option<String>,
 }
 
 #[derive(Event)]
 pub struct TncOutgoingEvent {
     pub message: String,
     pub message_type: MessageType,
+    pub destination: Option<String>,
 }
 
 // Channel resources for TNC communication
+// These stay valid across reconnects; the supervisor rebuilds the link behind them.
+// Dropping them is what stops the TNC threads (see `shutdown`).
 #[derive(Resource)]
 pub struct TncChannels {
     pub sender: Sender<GameMessage>,
     pub receiver: Receiver<GameMessage>,
+    pub status: Receiver<LinkStatus>,
+    pub retry: Sender<()>,
+    // Frames waiting in the sender's scheduler
+    scheduled: Arc<AtomicUsize>,
+    duty_cycle: Arc<Mutex<DutyCycle>>,
+    supervisor: thread::JoinHandle<()>,
+}
+
+impl TncChannels {
+    // Frames handed over by the game that haven't gone out yet
+    pub fn queue_depth(&self) -> usize {
+        self.sender.len() + self.scheduled.load(Ordering::Relaxed)
+    }
+
+    // Share of the recent channel time we spent transmitting, and the limit
+    pub fn airtime_usage(&self) -> (f32, f32) {
+        let duty_cycle = self.duty_cycle.lock().unwrap();
+        (duty_cycle.usage(Instant::now()), duty_cycle.max())
+    }
+
+    // Close the channels and wait for the threads to finish. The sender thread
+    // transmits whatever is still queued before it notices the game is gone.
+    pub fn shutdown(self) {
+        let TncChannels {
+            sender,
+            receiver,
+            status,
+            retry,
+            scheduled,
+            duty_cycle: _,
+            supervisor,
+        } = self;
+        let queued = sender.len() + scheduled.load(Ordering::Relaxed);
+        drop((sender, receiver, status, retry));
+
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        while !supervisor.is_finished() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+        if supervisor.is_finished() {
+            println!("[i] TNC threads stopped ({} queued frames flushed)", queued);
+        } else {
+            // Most likely stuck connecting; it exits as soon as that returns
+            println!("[!] TNC threads still busy after {} seconds, leaving them behind", SHUTDOWN_TIMEOUT.as_secs());
+        }
+    }
+}
+
+// State of the TNC link as reported by the connection supervisor
+#[derive(Resource, Clone, Debug)]
+pub enum LinkStatus {
+    Connecting,
+    Connected,
+    Reconnecting {
+        attempt: u32,
+        next_attempt: Instant,
+        last_error: String,
+    },
+    Failed {
+        last_error: String,
+    },
 }
 
 pub struct TncPlugin;
@@ -39,47 +122,70 @@ impl Plugin for TncPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<TncIncomingEvent>()
             .add_event::<TncOutgoingEvent>()
+            .insert_resource(LinkStatus::Connecting)
+            .init_resource::<TncConnection>()
             .add_systems(OnEnter(AppState::InGame), setup_tnc_connection)
+            .add_systems(OnExit(AppState::InGame), stop_tnc_connection)
             .add_systems(
                 Update,
-                (handle_incoming_tnc_messages, handle_outgoing_tnc_messages)
+                (
+                    handle_incoming_tnc_messages,
+                    handle_outgoing_tnc_messages,
+                    update_link_status,
+                )
                     .run_if(in_state(AppState::InGame)),
             );
     }
 }
 
-fn setup_tnc_connection(mut commands: Commands, menu_config: Res<MenuConfig>) {
+fn setup_tnc_connection(
+    mut commands: Commands,
+    menu_config: Res<MenuConfig>,
+    mut tnc_connection: ResMut<TncConnection>,
+) {
     println!("[i] Setting up TNC connection...");
 
     // Create channels for communication between game and TNC thread
     let (tx_to_game, rx_from_tnc) = unbounded::<GameMessage>();
     let (tx_to_tnc, rx_from_game) = unbounded::<GameMessage>();
-
-    // Store channels as a resource
-    commands.insert_resource(TncChannels {
-        sender: tx_to_tnc.clone(),
-        receiver: rx_from_tnc,
-    });
+    let (tx_status, rx_status) = unbounded::<LinkStatus>();
+    let (tx_retry, rx_retry) = unbounded::<()>();
 
     let tnc_address_str = menu_config.get_tnc_address();
     let callsign = menu_config.callsign.clone();
+    let listen_only = menu_config.listen_only;
+    // Checked when Connect was pressed, so an error here is a bug
+    let station_settings = menu_config.get_modem_timing().and_then(|timing| {
+        Ok((modem_timing(&tnc_address_str, timing), menu_config.get_max_duty_cycle()?))
+    });
+    let scheduled = Arc::new(AtomicUsize::new(0));
+    let queue_depth = scheduled.clone();
+    let max_duty_cycle = station_settings.as_ref().map_or(0.0, |&(_, max)| max);
+    let duty_cycle = Arc::new(Mutex::new(DutyCycle::new(max_duty_cycle)));
+    let airtime_budget = duty_cycle.clone();
+    // Reuse the connection the menu already validated; only reconnects open new ones
+    let mut validated = tnc_connection.0.take();
 
-    // Connect to TNC in a separate thread to avoid blocking the game
-    thread::spawn(move || {
-        // Parse TNC address
-        let addr = match tnc_address_str.parse::<TncAddress>() {
-            Ok(addr) => addr,
-            Err(e) => {
-                println!("[!] Failed to parse TNC address: {}", e);
+    // Supervise the TNC connection in a separate thread to avoid blocking the game
+    let supervisor = thread::spawn(move || {
+        // Never transmit with modem or duty cycle settings we couldn't read
+        let (bit_rate, txdelay_ms) = match station_settings {
+            Ok((timing, _)) => timing,
+            Err(last_error) => {
+                println!("[!] {}", last_error);
+                let _ = tx_status.send(LinkStatus::Failed { last_error });
                 return;
             }
         };
 
-        // Parse callsign as AX.25 address
-        let source_addr = match callsign.parse::<Address>() {
-            Ok(addr) => addr,
+        // Parse callsign as AX.25 address. Listeners may not have one.
+        let source_addr = match parse_callsign(&callsign) {
+            Ok(addr) => Some(addr),
+            Err(_) if listen_only => None,
             Err(e) => {
-                println!("[!] Failed to parse callsign: {}", e);
+                let last_error = format!("Failed to parse callsign: {}", e);
+                println!("[!] {}", last_error);
+                let _ = tx_status.send(LinkStatus::Failed { last_error });
                 return;
             }
         };
@@ -88,89 +194,307 @@ fn setup_tnc_connection(mut commands: Commands, menu_config: Res<MenuConfig>) {
         let dest_addr = match "HAMRPG-0".parse::<Address>() {
             Ok(addr) => addr,
             Err(e) => {
-                println!("[!] Failed to parse destination address: {}", e);
+                let last_error = format!("Failed to parse destination address: {}", e);
+                println!("[!] {}", last_error);
+                let _ = tx_status.send(LinkStatus::Failed { last_error });
                 return;
             }
         };
 
-        // Connect to TNC
-        println!("[i] Connecting to TNC: {}", tnc_address_str);
-        let tnc = match Tnc::open(&addr) {
-            Ok(tnc) => {
-                println!("[i] Connected to TNC successfully!");
-                tnc
+        // Outlives each link so frames waiting when it drops go out on the next one
+        let mut queue = OutgoingQueue::default();
+        let mut attempt = 0;
+        loop {
+            let opened = match validated.take() {
+                Some(transport) => Ok(transport),
+                None => {
+                    // Connect to TNC
+                    println!("[i] Connecting to TNC: {}", tnc_address_str);
+                    open_transport(&tnc_address_str)
+                }
+            };
+            let last_error = match opened {
+                Ok(transport) => {
+                    println!("[i] Connected to TNC successfully!");
+                    attempt = 0;
+                    if tx_status.send(LinkStatus::Connected).is_err() {
+                        return;
+                    }
+                    let link = Link {
+                        transport: Arc::from(transport),
+                        source: source_addr.clone(),
+                        destination: dest_addr.clone(),
+                        bit_rate,
+                        txdelay_ms,
+                    };
+                    let outgoing = Outgoing {
+                        queue: &mut queue,
+                        depth: &queue_depth,
+                        duty_cycle: &airtime_budget,
+                    };
+                    match link.run(&tx_to_game, &rx_from_game, outgoing) {
+                        Some(error) => error,
+                        // The game dropped its channels - nothing left to serve
+                        None => return,
+                    }
+                }
+                Err(e) => format!("Failed to connect to TNC: {}", e),
+            };
+            println!("[!] {}", last_error);
+
+            attempt += 1;
+            if attempt > MAX_RECONNECT_ATTEMPTS {
+                println!("[!] Giving up on TNC after {} attempts", MAX_RECONNECT_ATTEMPTS);
+                if tx_status.send(LinkStatus::Failed { last_error }).is_err() {
+                    return;
+                }
+                // Wait for the player to ask for another try
+                if rx_retry.recv().is_err() {
+                    return;
+                }
+                attempt = 0;
+                let _ = tx_status.send(LinkStatus::Connecting);
+                continue;
+            }
+
+            let delay = reconnect_delay(attempt);
+            println!("[i] Reconnecting in {} seconds (attempt {})", delay.as_secs(), attempt);
+            let status = LinkStatus::Reconnecting {
+                attempt,
+                next_attempt: Instant::now() + delay,
+                last_error,
+            };
+            if tx_status.send(status).is_err() {
+                return;
             }
-            Err(e) => {
-                println!("[!] Failed to connect to TNC: {}", e);
+            // A retry request cuts the wait short
+            if let Err(crossbeam_channel::RecvTimeoutError::Disconnected) =
+                rx_retry.recv_timeout(delay)
+            {
                 return;
             }
-        };
+        }
+    });
+
+    // Store channels as a resource
+    commands.insert_resource(TncChannels {
+        sender: tx_to_tnc,
+        receiver: rx_from_tnc,
+        status: rx_status,
+        retry: tx_retry,
+        scheduled,
+        duty_cycle,
+        supervisor,
+    });
+    commands.insert_resource(LinkStatus::Connecting);
+}
+
+// Leaving the game: hand over messages the game queued but nobody picked up yet,
+// then flush and stop the TNC threads so the next game can open a fresh link
+pub fn stop_tnc_connection(world: &mut World) {
+    let pending: Vec<TncOutgoingEvent> = world
+        .resource_mut::<Events<TncOutgoingEvent>>()
+        .drain()
+        .collect();
+    // Whatever was heard belongs to the session that is ending
+    world.resource_mut::<Events<TncIncomingEvent>>().clear();
+    let listen_only = world.get_resource::<ListenOnly>().is_some_and(|listen_only| listen_only.0);
+    if let Some(tnc_channels) = world.remove_resource::<TncChannels>() {
+        println!("[i] Closing TNC connection...");
+        if !listen_only {
+            for event in pending {
+                let _ = tnc_channels.sender.send(GameMessage {
+                    content: event.message,
+                    message_type: event.message_type,
+                    destination: event.destination,
+                });
+            }
+        }
+        tnc_channels.shutdown();
+    }
+    world.insert_resource(LinkStatus::Connecting);
+}
+
+// Bit rate and TXDELAY of the channel behind a TNC address. Simulated channels
+// say so in the address; for a real modem use what the station is set up with.
+fn modem_timing(address: &str, configured: (u32, u32)) -> (u32, u32) {
+    address
+        .strip_prefix("sim:")
+        .and_then(|spec| SimConfig::parse(spec).ok())
+        .map_or(configured, |(_, config)| (config.bit_rate, config.txdelay_ms))
+}
+
+fn reconnect_delay(attempt: u32) -> Duration {
+    RECONNECT_BASE_DELAY
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(RECONNECT_MAX_DELAY)
+}
 
-        let tnc = Arc::new(Mutex::new(tnc));
-        let tnc_clone = Arc::clone(&tnc);
+// The sender's scheduling state, which outlives each link
+struct Outgoing<'a> {
+    queue: &'a mut OutgoingQueue,
+    // Frames in the queue, for the HUD
+    depth: &'a AtomicUsize,
+    duty_cycle: &'a Mutex<DutyCycle>,
+}
+
+// One live connection to the TNC
+struct Link {
+    transport: Arc<dyn Transport>,
+    // None for listen-only stations, which never transmit
+    source: Option<Address>,
+    destination: Address,
+    // Used to pace frames to what the channel can carry
+    bit_rate: u32,
+    txdelay_ms: u32,
+}
 
-        // Spawn a thread to listen for incoming frames from TNC
-        let tx_to_game_clone = tx_to_game.clone();
-        thread::spawn(move || {
+impl Link {
+    // Pump frames until the link fails (returns the reason) or the game goes away (None)
+    fn run(
+        self,
+        tx_to_game: &Sender<GameMessage>,
+        rx_from_game: &Receiver<GameMessage>,
+        outgoing: Outgoing,
+    ) -> Option<String> {
+        // Spawn a thread to listen for incoming frames from TNC.
+        // Dropping `link_alive` tells the sender loop the receiver has stopped,
+        // and dropping `stop` stops the receiver.
+        let (link_alive, link_down) = unbounded::<()>();
+        let (stop, stopped) = unbounded::<()>();
+        let receiver = self.transport.incoming();
+        let tx_to_game = tx_to_game.clone();
+        let broadcast = self.destination.clone();
+        let receiver_thread = thread::spawn(move || {
             println!("[i] Starting TNC receiver thread...");
-            let receiver = tnc_clone.lock().unwrap().incoming();
-
-            while let Ok(frame) = receiver.recv().unwrap() {
-                if let Some(frame_data) = frame.info_string_lossy() {
-                    println!("[i] Received from TNC: {} bytes - {}", frame_data.len(), frame_data);
-
-                    // Decode the packet using our custom protocol
-                    match decode_packet(&frame_data) {
-                        Ok(decoded) => {
-                            use crate::connection::compression::DecodedPacket;
-
-                            let game_message = match decoded {
-                                DecodedPacket::Position(pos_data) => {
-                                    // Convert position data to JSON string for compatibility
-                                    match serde_json::to_string(&pos_data) {
-                                        Ok(json) => GameMessage {
-                                            content: json,
-                                            message_type: MessageType::Position,
-                                        },
-                                        Err(e) => {
-                                            println!("[!] Failed to serialize position: {}", e);
-                                            continue;
-                                        }
-                                    }
-                                }
-                                DecodedPacket::Chat(message) => GameMessage {
-                                    content: message,
-                                    message_type: MessageType::Chat,
-                                },
-                            };
-
-                            if let Err(e) = tx_to_game_clone.send(game_message) {
-                                println!("[!] Failed to send message to game: {}", e);
-                                break;
-                            }
-                        }
-                        Err(e) => {
-                            println!("[!] Failed to decode packet: {}", e);
+            let _link_alive = link_alive;
+
+            loop {
+                let frame = select! {
+                    recv(receiver) -> frame => match frame {
+                        Ok(Ok(frame)) => frame,
+                        Ok(Err(e)) => {
+                            println!("[!] Failed to parse frame from TNC: {}", e);
+                            continue;
                         }
+                        Err(_) => break,
+                    },
+                    recv(stopped) -> _ => break,
+                };
+
+                if let Some(mut game_message) = frame_to_game_message(&frame) {
+                    // Anything not sent to every player is a direct message
+                    game_message.destination =
+                        (frame.destination != broadcast).then(|| frame.destination.to_string());
+                    if let Err(e) = tx_to_game.send(game_message) {
+                        println!("[!] Failed to send message to game: {}", e);
+                        break;
                     }
                 }
             }
             println!("[!] TNC receiver thread ended");
         });
 
-        // Process outgoing messages in this thread
+        // Process outgoing messages in this thread, then take the receiver down
+        // with it
+        let result = self.send_frames(rx_from_game, &link_down, outgoing);
+        drop(stop);
+        let _ = receiver_thread.join();
+        result
+    }
+
+    // Transmit messages from the game until the link fails (returns the reason)
+    // or the game goes away and the queue has been worked off (None).
+    // Frames are held in the queue while our previous frame is still on air or
+    // the duty cycle budget is spent, so chat can overtake positions and stale
+    // positions can be replaced. Once the game is gone only presence frames (the
+    // leave packet) still go out, and only within the budget.
+    fn send_frames(
+        &self,
+        rx_from_game: &Receiver<GameMessage>,
+        link_down: &Receiver<()>,
+        outgoing: Outgoing,
+    ) -> Option<String> {
         println!("[i] Starting TNC sender thread...");
-        for message in rx_from_game {
-            // The message content is already in the internal format (JSON for position)
-            // We need to re-encode it using our custom protocol
-            let encoded_data = message.content;
+        let Outgoing {
+            queue,
+            depth,
+            duty_cycle,
+        } = outgoing;
+        let mut channel_free = Instant::now();
+        loop {
+            // Take everything the game has handed over so far
+            let mut game_gone = false;
+            loop {
+                match rx_from_game.try_recv() {
+                    Ok(message) => queue.push(message),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        game_gone = true;
+                        break;
+                    }
+                }
+            }
+            depth.store(queue.len(), Ordering::Relaxed);
 
-            println!("[i] Sending to TNC: {} bytes - {}", encoded_data.len(), encoded_data);
+            if queue.is_empty() {
+                if game_gone {
+                    return None;
+                }
+                select! {
+                    recv(rx_from_game) -> message => match message {
+                        Ok(message) => queue.push(message),
+                        Err(_) => return None,
+                    },
+                    recv(link_down) -> _ => return Some("TNC connection lost".to_string()),
+                }
+                continue;
+            }
+
+            // Wait for the previous frame to clear the channel
+            let wait = channel_free.saturating_duration_since(Instant::now());
+            if !wait.is_zero() {
+                if game_gone {
+                    thread::sleep(wait);
+                } else if let Err(e) = wait_for_channel(rx_from_game, link_down, queue, wait) {
+                    return Some(e);
+                }
+                continue;
+            }
+
+            let Some(message) = queue.pop() else {
+                continue;
+            };
+            depth.store(queue.len(), Ordering::Relaxed);
+
+            // Positions and chat retries are stale once we are leaving
+            if game_gone && !matches!(message.message_type, MessageType::Presence) {
+                println!("[i] Leaving, dropping queued {:?} frame", message.message_type);
+                continue;
+            }
+
+            let Some(source) = &self.source else {
+                println!("[!] Listen-only station, not transmitting");
+                continue;
+            };
+
+            // Direct messages are addressed to their station, the rest to every player
+            let destination = match message.destination.as_deref().map(parse_callsign) {
+                None => self.destination.clone(),
+                Some(Ok(address)) => address,
+                Some(Err(e)) => {
+                    println!("[!] Not sending to invalid address {:?}: {}", message.destination, e);
+                    continue;
+                }
+            };
+
+            // The message content is already encoded with our custom protocol
+            let encoded_data = &message.content;
 
             // Construct AX.25 frame
             let frame = Ax25Frame {
-                source: source_addr.clone(),
-                destination: dest_addr.clone(),
+                source: source.clone(),
+                destination,
                 route: Vec::new(),
                 command_or_response: Some(CommandResponse::Command),
                 content: FrameContent::UnnumberedInformation(UnnumberedInformation {
@@ -179,19 +503,147 @@ fn setup_tnc_connection(mut commands: Commands, menu_config: Res<MenuConfig>) {
                     poll_or_final: false,
                 }),
             };
+            let airtime = frame_airtime(frame.to_bytes().len(), self.bit_rate, self.txdelay_ms);
+
+            // Hold the frame until it fits in the airtime budget
+            let over_budget = duty_cycle
+                .lock()
+                .unwrap()
+                .wait_for(Instant::now(), airtime, &message.message_type);
+            if !over_budget.is_zero() {
+                if game_gone {
+                    println!(
+                        "[!] Airtime budget spent, not sending {:?} frame on the way out",
+                        message.message_type
+                    );
+                    continue;
+                }
+                println!(
+                    "[i] Airtime budget spent, holding {:?} frame for {} seconds",
+                    message.message_type,
+                    over_budget.as_secs()
+                );
+                queue.requeue(message);
+                depth.store(queue.len(), Ordering::Relaxed);
+                if let Err(e) = wait_for_channel(rx_from_game, link_down, queue, over_budget) {
+                    return Some(e);
+                }
+                continue;
+            }
+
+            println!("[i] Sending to TNC: {} bytes - {}", encoded_data.len(), encoded_data);
 
             // Send frame to TNC
-            match tnc.lock().unwrap().send_frame(&frame) {
+            match self.transport.send_frame(&frame) {
                 Ok(_) => {
                     println!("[i] Frame sent successfully");
+                    channel_free = channel_free.max(Instant::now()) + airtime;
+                    duty_cycle.lock().unwrap().record(channel_free, airtime);
                 }
                 Err(e) => {
-                    println!("[!] Failed to send frame to TNC: {}", e);
+                    // Keep the frame for the next link
+                    queue.requeue(message);
+                    depth.store(queue.len(), Ordering::Relaxed);
+                    return Some(format!("Failed to send frame to TNC: {}", e));
                 }
             }
         }
-        println!("[!] TNC sender thread ended");
-    });
+    }
+}
+
+// Sleep for up to `wait` while taking in new messages from the game. Fails if
+// the link goes down meanwhile.
+fn wait_for_channel(
+    rx_from_game: &Receiver<GameMessage>,
+    link_down: &Receiver<()>,
+    queue: &mut OutgoingQueue,
+    wait: Duration,
+) -> Result<(), String> {
+    select! {
+        recv(rx_from_game) -> message => {
+            // A closed channel is noticed by the caller
+            if let Ok(message) = message {
+                queue.push(message);
+            }
+        },
+        recv(link_down) -> _ => return Err("TNC connection lost".to_string()),
+        default(wait) => {},
+    }
+    Ok(())
+}
+
+// Decode a received frame into a message for the game, if it is one of ours
+fn frame_to_game_message(frame: &Ax25Frame) -> Option<GameMessage> {
+    let frame_data = frame.info_string_lossy()?;
+    println!("[i] Received from TNC: {} bytes - {}", frame_data.len(), frame_data);
+
+    // Decode the packet using our custom protocol
+    match decode_packet(&frame_data) {
+        Ok(decoded) => {
+            use crate::connection::compression::DecodedPacket;
+
+            match decoded {
+                DecodedPacket::Position(pos_data) => {
+                    // Convert position data to JSON string for compatibility
+                    match serde_json::to_string(&pos_data) {
+                        Ok(json) => Some(GameMessage {
+                            content: json,
+                            message_type: MessageType::Position,
+                            destination: None,
+                        }),
+                        Err(e) => {
+                            println!("[!] Failed to serialize position: {}", e);
+                            None
+                        }
+                    }
+                }
+                DecodedPacket::Chat(message) => json_message(&message, MessageType::Chat),
+                DecodedPacket::ChatFragment(fragment) => {
+                    json_message(&fragment, MessageType::ChatFragment)
+                }
+                DecodedPacket::Ack(ack) => json_message(&ack, MessageType::Ack),
+                DecodedPacket::Hello { callsign, profile } => {
+                    presence_message(Presence::Hello { callsign, profile })
+                }
+                DecodedPacket::Leave { callsign } => presence_message(Presence::Leave { callsign }),
+                DecodedPacket::RosterQuery { callsign } => {
+                    presence_message(Presence::RosterQuery { callsign })
+                }
+            }
+        }
+        Err(e) => {
+            println!("[!] Failed to decode packet: {}", e);
+            None
+        }
+    }
+}
+
+fn json_message<T: serde::Serialize>(value: &T, message_type: MessageType) -> Option<GameMessage> {
+    match serde_json::to_string(value) {
+        Ok(json) => Some(GameMessage {
+            content: json,
+            message_type,
+            destination: None,
+        }),
+        Err(e) => {
+            println!("[!] Failed to serialize {:?}: {}", message_type, e);
+            None
+        }
+    }
+}
+
+fn presence_message(presence: Presence) -> Option<GameMessage> {
+    match serde_json::to_string(&presence) {
+        Ok(json) => Some(GameMessage {
+            content: json,
+            message_type: MessageType::Presence,
+            destination: None,
+        }),
+        Err(e) => {
+            println!("[!] Failed to serialize presence: {}", e);
+            None
+        }
+    }
 }
 
 fn handle_incoming_tnc_messages(
@@ -204,6 +656,7 @@ fn handle_incoming_tnc_messages(
             event_writer.write(TncIncomingEvent {
                 message: message.content,
                 message_type: message.message_type,
+                destination: message.destination,
             });
         }
     }
@@ -212,12 +665,20 @@ fn handle_incoming_tnc_messages(
 fn handle_outgoing_tnc_messages(
     mut events: EventReader<TncOutgoingEvent>,
     tnc_channels: Option<Res<TncChannels>>,
+    listen_only: Res<ListenOnly>,
 ) {
+    // Receive-only stations must never key up
+    if listen_only.0 {
+        events.clear();
+        return;
+    }
+
     if let Some(tnc_channels) = tnc_channels {
         for event in events.read() {
             let game_message = GameMessage {
                 content: event.message.clone(),
                 message_type: event.message_type.clone(),
+                destination: event.destination.clone(),
             };
 
             if let Err(e) = tnc_channels.sender.send(game_message) {
@@ -226,3 +687,12 @@ fn handle_outgoing_tnc_messages(
         }
     }
 }
+
+// Mirror the supervisor's latest report into the LinkStatus resource
+fn update_link_status(tnc_channels: Option<Res<TncChannels>>, mut link_status: ResMut<LinkStatus>) {
+    if let Some(tnc_channels) = tnc_channels {
+        if let Some(status) = tnc_channels.status.try_iter().last() {
+            *link_status = status;
+        }
+    }
+}
@@ -0,0 +1,239 @@
+// Link-layer transports that carry AX.25 frames between the game and a modem
+// TncPlugin only talks to the Transport trait, so new modems plug in here
+
+use super::kiss::{KissDecoder, data_payload, encode_frame};
+use super::sim::SimTransport;
+use ax25::frame::Ax25Frame;
+use ax25_tnc::tnc::{Tnc, TncAddress};
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use std::io::{ErrorKind, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// A frame (or a decode error) heard on the channel
+pub type IncomingFrame = Result<Ax25Frame, String>;
+
+pub trait Transport: Send + Sync {
+    // Hand a frame to the modem for transmission
+    fn send_frame(&self, frame: &Ax25Frame) -> Result<(), String>;
+
+    // Frames heard on the channel. The receiver disconnects when the link is lost.
+    fn incoming(&self) -> Receiver<IncomingFrame>;
+}
+
+// Open a transport from an address string:
+//   tnc:tcpkiss:HOST:PORT   KISS over TCP (direwolf, SoundModem, VARA)
+//   serial:DEVICE:BAUD      KISS over a serial port (hardware TNC)
+//   sim:BITRATE:CHANNEL[:TXDELAY_MS[:LOSS_PCT]]
+//                           simulated shared radio channel (see sim.rs)
+//   loopback                in-memory, every sent frame is heard back
+pub fn open_transport(address: &str) -> Result<Box<dyn Transport>, String> {
+    if address == "loopback" {
+        return Ok(Box::new(LoopbackTransport::new()));
+    }
+
+    if let Some(spec) = address.strip_prefix("sim:") {
+        return Ok(Box::new(SimTransport::open(spec)?));
+    }
+
+    if let Some(serial) = address.strip_prefix("serial:") {
+        let (device, baud) = serial
+            .rsplit_once(':')
+            .ok_or_else(|| format!("Invalid serial address: {}", address))?;
+        let baud = baud
+            .parse::<u32>()
+            .map_err(|_| format!("Invalid baud rate: {}", baud))?;
+        return Ok(Box::new(SerialKissTransport::open(device, baud)?));
+    }
+
+    let addr = address
+        .parse::<TncAddress>()
+        .map_err(|e| format!("Invalid TNC address: {}", e))?;
+    Ok(Box::new(TcpKissTransport::open(&addr)?))
+}
+
+// KISS over TCP through ax25_tnc
+pub struct TcpKissTransport {
+    tnc: Mutex<Tnc>,
+    receiver: Receiver<IncomingFrame>,
+}
+
+impl TcpKissTransport {
+    pub fn open(address: &TncAddress) -> Result<Self, String> {
+        let tnc = Tnc::open(address).map_err(|e| e.to_string())?;
+
+        // Bridge ax25_tnc's receiver onto a crossbeam channel, once per connection
+        let tnc_receiver = tnc.incoming();
+        let (tx, rx) = unbounded();
+        thread::spawn(move || {
+            while let Ok(frame) = tnc_receiver.recv() {
+                if tx.send(frame.map_err(|e| e.to_string())).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            tnc: Mutex::new(tnc),
+            receiver: rx,
+        })
+    }
+}
+
+impl Transport for TcpKissTransport {
+    fn send_frame(&self, frame: &Ax25Frame) -> Result<(), String> {
+        self.tnc
+            .lock()
+            .unwrap()
+            .send_frame(frame)
+            .map_err(|e| e.to_string())
+    }
+
+    fn incoming(&self) -> Receiver<IncomingFrame> {
+        self.receiver.clone()
+    }
+}
+
+// KISS over a serial port, for hardware TNCs
+pub struct SerialKissTransport {
+    port: Mutex<Box<dyn serialport::SerialPort>>,
+    receiver: Receiver<IncomingFrame>,
+    // Tells the reader thread to let go of the port
+    closed: Arc<AtomicBool>,
+}
+
+impl SerialKissTransport {
+    pub fn open(device: &str, baud: u32) -> Result<Self, String> {
+        let port = serialport::new(device, baud)
+            .timeout(Duration::from_millis(100))
+            .open()
+            .map_err(|e| format!("Failed to open {}: {}", device, e))?;
+        let mut reader = port.try_clone().map_err(|e| e.to_string())?;
+
+        let (tx, rx) = unbounded();
+        let closed = Arc::new(AtomicBool::new(false));
+        let reader_closed = closed.clone();
+        thread::spawn(move || {
+            let mut decoder = KissDecoder::default();
+            let mut buf = [0u8; 256];
+            while !reader_closed.load(Ordering::Relaxed) {
+                let n = match reader.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => n,
+                    Err(e) if e.kind() == ErrorKind::TimedOut => continue,
+                    Err(e) => {
+                        println!("[!] Serial TNC read failed: {}", e);
+                        break;
+                    }
+                };
+                for &byte in &buf[..n] {
+                    let Some(frame) = decoder.push(byte) else {
+                        continue;
+                    };
+                    let Some(payload) = data_payload(&frame) else {
+                        continue;
+                    };
+                    let decoded = Ax25Frame::from_bytes(payload).map_err(|e| e.to_string());
+                    if tx.send(decoded).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            port: Mutex::new(port),
+            receiver: rx,
+            closed,
+        })
+    }
+}
+
+impl Drop for SerialKissTransport {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Transport for SerialKissTransport {
+    fn send_frame(&self, frame: &Ax25Frame) -> Result<(), String> {
+        let mut port = self.port.lock().unwrap();
+        port.write_all(&encode_frame(&frame.to_bytes()))
+            .and_then(|_| port.flush())
+            .map_err(|e| e.to_string())
+    }
+
+    fn incoming(&self) -> Receiver<IncomingFrame> {
+        self.receiver.clone()
+    }
+}
+
+// In-memory transport for tests and offline play: every sent frame is heard back
+pub struct LoopbackTransport {
+    outgoing: Sender<IncomingFrame>,
+    receiver: Receiver<IncomingFrame>,
+}
+
+impl Default for LoopbackTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LoopbackTransport {
+    pub fn new() -> Self {
+        let (tx, rx) = unbounded();
+        Self {
+            outgoing: tx,
+            receiver: rx,
+        }
+    }
+}
+
+impl Transport for LoopbackTransport {
+    fn send_frame(&self, frame: &Ax25Frame) -> Result<(), String> {
+        self.outgoing
+            .send(Ok(frame.clone()))
+            .map_err(|_| "Loopback peer disconnected".to_string())
+    }
+
+    fn incoming(&self) -> Receiver<IncomingFrame> {
+        self.receiver.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ax25::frame::{CommandResponse, FrameContent, ProtocolIdentifier, UnnumberedInformation};
+
+    fn frame(info: &str) -> Ax25Frame {
+        Ax25Frame {
+            source: "N0CALL-1".parse().unwrap(),
+            destination: "HAMRPG-0".parse().unwrap(),
+            route: Vec::new(),
+            command_or_response: Some(CommandResponse::Command),
+            content: FrameContent::UnnumberedInformation(UnnumberedInformation {
+                pid: ProtocolIdentifier::None,
+                info: info.as_bytes().to_vec(),
+                poll_or_final: false,
+            }),
+        }
+    }
+
+    #[test]
+    fn loopback_hears_sent_frames() {
+        let transport = open_transport("loopback").unwrap();
+        let incoming = transport.incoming();
+        transport.send_frame(&frame("{H1 first")).unwrap();
+        transport.send_frame(&frame("{H1 second")).unwrap();
+
+        for expected in ["{H1 first", "{H1 second"] {
+            let heard = incoming.recv_timeout(Duration::from_secs(1)).unwrap().unwrap();
+            assert_eq!(heard.to_bytes(), frame(expected).to_bytes());
+        }
+        assert!(incoming.try_recv().is_err());
+    }
+}
@@ -1,3 +1,13 @@
 //Player Speed, and Camera Smoothing Constants
 pub const SPEED: f32 = 50.0;
+// Player sprites sort by their feet, this far below the sprite center
+pub const PLAYER_FOOT_OFFSET: f32 = -8.0;
 pub const CAMERA_DECAY_RATE: f32 = 2.0;
+// Free camera speed in listen-only mode
+pub const SPECTATOR_SPEED: f32 = 150.0;
+// Longest a remote player is moved along their last velocity without a new fix (seconds)
+pub const REMOTE_EXTRAPOLATION_HORIZON: f32 = 4.0;
+// Longest a remote player may take walking a path around obstacles to a new fix (seconds)
+pub const REMOTE_PATH_MAX_DURATION: f32 = 10.0;
+// Fade out and back in when a remote player has no walkable path to a new fix (seconds)
+pub const REMOTE_TELEPORT_FADE: f32 = 0.6;
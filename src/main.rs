@@ -1,6 +1,7 @@
 // HamRPG - Multiplayer 2D RPG over amateur radio
 // Uses KISS protocol to communicate with TNC software for AX.25 packet transmission
 mod components;
+mod config;
 mod connection;
 mod constants;
 mod menu;
@@ -10,34 +11,38 @@ use avian2d::prelude::*;
 use bevy::prelude::*;
 use bevy_ecs_tiled::prelude::*;
 use bevy_egui::EguiPlugin;
-use connection::resources::PositionUpdateTime;
-use connection::tnc_integration::{GameState, handle_tnc_events, send_position_updates};
-use connection::tnc_plugin::TncPlugin;
-use ini::Ini;
+use config::{GameConfig, Settings};
+use connection::beacon::BeaconSchedule;
+use connection::delivery::{ChatDelivery, update_chat_delivery};
+use connection::reassembly::ChatReassembly;
+use connection::resources::{ListenOnly, Paclen, PositionUpdateTime};
+use connection::tnc_integration::{
+    GameState, PendingRosterReply, announce_presence, answer_roster_queries, handle_tnc_events,
+    send_leave_on_exit, send_leave_on_quit, send_position_updates,
+};
+use connection::tnc_plugin::{TncPlugin, stop_tnc_connection};
 use iyes_perf_ui::prelude::*;
 use menu::{AppState, MenuConfig, display_menu, validate_connection};
 use systems::audio::play_background_audio;
 use systems::animation::{animate_sprite, update_animation};
-use systems::camera::update_camera;
-use systems::gui::{ChatInputState, chat_window, display_player_callsigns};
-use systems::player::{add_player, move_player};
+use systems::camera::{clamp_camera, spectator_camera, update_camera};
+use systems::gui::{
+    ChatInputState, chat_window, display_player_callsigns, link_status_hud, roster_window,
+};
+use systems::player::{add_player, clamp_player, move_player};
 use systems::remote_player::{cleanup_inactive_players, update_remote_player_movement};
-use systems::setup::{send_welcome_message, setup, adjust_layer_z_ordering};
+use systems::save::{SaveGame, autosave, save_game_on_exit, save_game_on_quit, track_progress};
+use systems::settings::{SettingsWindowState, apply_settings, settings_window};
+use systems::setup::{adjust_layer_z_ordering, despawn_game_world, send_welcome_message, setup};
+use systems::world_map::{WorldMap, load_world_map, spawn_world_map, y_sort_sprites};
+use systems::zones::{ZoneChange, change_zone, use_portals};
 
 fn main() {
-    // Load configuration from game_config.ini if available
-    let menu_config = if let Ok(conf) = Ini::load_from_file("game_config.ini") {
-        let game_info = conf.section(Some("Game")).unwrap();
-        let callsign = game_info.get("callsign").unwrap_or("N0CALL-1").to_string();
-        let pos_update_time = game_info.get("position_update_time").unwrap_or("30").to_string();
-
-        let mut config = MenuConfig::new();
-        config.callsign = callsign;
-        config.position_update_time = pos_update_time;
-        config
-    } else {
-        MenuConfig::new()
-    };
+    // Load station profiles from game_config.ini and start with the active one
+    let game_config = GameConfig::load();
+    let mut menu_config = MenuConfig::new();
+    game_config.active_station().apply_to(&mut menu_config);
+    menu_config.station_name = game_config.active.clone();
 
     App::new()
         .add_plugins((
@@ -53,7 +58,11 @@ fn main() {
         ))
         .init_state::<AppState>()
         .insert_resource(menu_config)
+        .insert_resource(game_config)
+        .insert_resource(Settings::load())
+        .insert_resource(SettingsWindowState::default())
         .insert_resource(ChatInputState::default())
+        .add_event::<ZoneChange>()
         // Menu state systems
         .add_systems(Update, display_menu.run_if(in_state(AppState::Menu)))
         .add_systems(Update, validate_connection.run_if(in_state(AppState::Menu)))
@@ -61,39 +70,118 @@ fn main() {
         .add_systems(OnEnter(AppState::InGame), setup_game_state)
         .add_systems(OnEnter(AppState::InGame), (
             setup,
-            add_player,
+            load_world_map,
             send_welcome_message,
+            announce_presence,
             play_background_audio,
         ).after(setup_game_state))
+        .add_systems(
+            OnEnter(AppState::InGame),
+            (
+                spawn_world_map.after(load_world_map),
+                add_player.after(load_world_map).after(setup),
+            ),
+        )
+        // Back to the menu: say goodbye and save, flush and close the link, then
+        // clear the world and the session's state
+        .add_systems(
+            OnExit(AppState::InGame),
+            (
+                (send_leave_on_quit, save_game_on_quit).before(stop_tnc_connection),
+                (despawn_game_world, reset_game_state).after(stop_tnc_connection),
+            ),
+        )
         // Per-frame game loop systems
         .add_systems(
             Update,
             (
                 move_player,
+                clamp_player,
                 animate_sprite,
                 update_animation,
-                update_camera,
+                (update_camera, spectator_camera, clamp_camera).chain(),
                 handle_tnc_events,
                 send_position_updates,
+                answer_roster_queries,
                 update_remote_player_movement,
                 cleanup_inactive_players,
+                use_portals,
+                change_zone.after(use_portals),
                 chat_window,
+                link_status_hud,
+                roster_window,
                 display_player_callsigns,
                 adjust_layer_z_ordering,
             ).run_if(in_state(AppState::InGame)),
         )
+        .add_systems(
+            Update,
+            (
+                track_progress,
+                autosave.after(track_progress),
+                update_chat_delivery.after(handle_tnc_events),
+                settings_window.before(chat_window),
+                apply_settings.after(settings_window),
+            )
+                .run_if(in_state(AppState::InGame)),
+        )
+        // Depth follows the final position of the frame
+        .add_systems(
+            PostUpdate,
+            y_sort_sprites
+                .before(bevy::transform::TransformSystem::TransformPropagate)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Last,
+            (send_leave_on_exit, save_game_on_exit).run_if(in_state(AppState::InGame)),
+        )
         .run();
 }
 
 // Initialize game state resource from menu configuration
 fn setup_game_state(mut commands: Commands, menu_config: Res<MenuConfig>) {
+    let save_game = SaveGame::load(&menu_config.callsign);
     commands.insert_resource(GameState {
-        chat_messages: Vec::new(),
+        chat_messages: save_game.data.chat_history.clone(),
         known_players: Vec::new(),
         player_entities: std::collections::HashMap::new(),
         player_callsign: menu_config.callsign.clone(),
+        player_profile: (!menu_config.profile.is_empty()).then(|| menu_config.profile.clone()),
+        last_heard: std::collections::HashMap::new(),
+        last_fixes: std::collections::HashMap::new(),
+        direct_messages: save_game.data.direct_messages.clone(),
+        unread: std::collections::HashMap::new(),
     });
+    commands.insert_resource(PendingRosterReply::default());
+    commands.insert_resource(BeaconSchedule::default());
+    commands.insert_resource(ChatReassembly::default());
+    commands.insert_resource(ChatDelivery::default());
+    commands.insert_resource(save_game);
 
     // Update position update time from menu config
     commands.insert_resource(PositionUpdateTime(menu_config.get_position_update_time()));
+    commands.insert_resource(ListenOnly(menu_config.listen_only));
+    commands.insert_resource(Paclen(menu_config.get_paclen()));
+}
+
+// Drop everything setup_game_state made for the session, so the next game
+// starts clean with whatever callsign and link the menu gives it
+fn reset_game_state(
+    mut commands: Commands,
+    mut chat_state: ResMut<ChatInputState>,
+    mut settings_window: ResMut<SettingsWindowState>,
+) {
+    commands.remove_resource::<GameState>();
+    commands.remove_resource::<PendingRosterReply>();
+    commands.remove_resource::<BeaconSchedule>();
+    commands.remove_resource::<ChatReassembly>();
+    commands.remove_resource::<ChatDelivery>();
+    commands.remove_resource::<PositionUpdateTime>();
+    commands.remove_resource::<ListenOnly>();
+    commands.remove_resource::<Paclen>();
+    commands.remove_resource::<SaveGame>();
+    commands.remove_resource::<WorldMap>();
+    *chat_state = ChatInputState::default();
+    *settings_window = SettingsWindowState::default();
 }
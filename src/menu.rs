@@ -1,5 +1,12 @@
 use bevy::prelude::*;
 use bevy_egui::{egui, EguiContexts};
+use crate::config::{DEFAULT_PROFILE, GameConfig, StationProfile};
+use crate::connection::callsign::{normalize_callsign, parse_callsign};
+use crate::connection::resources::TncConnection;
+
+/// Frame sizes the PACLEN field accepts: enough for a fragment header and some
+/// text, up to the AX.25 maximum
+pub const PACLEN_RANGE: std::ops::RangeInclusive<usize> = 32..=256;
 
 /// Game states to differentiate between menu and gameplay
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
@@ -9,17 +16,38 @@ pub enum AppState {
     InGame,
 }
 
+/// How the game reaches the modem
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum LinkType {
+    #[default]
+    TcpKiss,
+    SerialKiss,
+    Simulated,
+}
+
 /// Resource to store menu input values
 #[derive(Resource)]
 pub struct MenuConfig {
     pub callsign: String,
+    pub link_type: LinkType,
     pub tnc_host: String,
     pub tnc_port: String,
     pub position_update_time: String,
+    pub listen_only: bool,
+    // Optional free-text profile sent with presence announcements
+    pub profile: String,
+    // Modem speed and key-up delay, used to estimate airtime
+    pub bit_rate: String,
+    pub txdelay_ms: String,
+    // Most of the channel time we may transmit, in percent
+    pub max_duty_cycle: String,
+    // Longest frame the modem takes; longer chat is split
+    pub paclen: String,
+    // Name of the station profile being edited
+    pub station_name: String,
     pub connect_clicked: bool,
     pub connection_error: Option<String>,
     pub is_connecting: bool,
-    pub validation_time: Option<std::time::Instant>,
 }
 
 impl Default for MenuConfig {
@@ -32,29 +60,83 @@ impl MenuConfig {
     pub fn new() -> Self {
         Self {
             callsign: "N0CALL-1".to_string(),
+            link_type: LinkType::TcpKiss,
             tnc_host: "127.0.0.1".to_string(),
             tnc_port: "8100".to_string(),
             position_update_time: "30".to_string(),
+            listen_only: false,
+            profile: String::new(),
+            bit_rate: "1200".to_string(),
+            txdelay_ms: "300".to_string(),
+            max_duty_cycle: "100".to_string(),
+            paclen: "128".to_string(),
+            station_name: DEFAULT_PROFILE.to_string(),
             connect_clicked: false,
             connection_error: None,
             is_connecting: false,
-            validation_time: None,
         }
     }
 
+    /// Transport address understood by `open_transport`.
+    /// For serial links the host field holds the device and the port field the baud rate.
+    /// For simulated links the host field holds `CHANNEL[:TXDELAY_MS[:LOSS_PCT]]`
+    /// and the port field the bit rate.
     pub fn get_tnc_address(&self) -> String {
-        format!("tnc:tcpkiss:{}:{}", self.tnc_host, self.tnc_port)
+        match self.link_type {
+            LinkType::TcpKiss => format!("tnc:tcpkiss:{}:{}", self.tnc_host, self.tnc_port),
+            LinkType::SerialKiss => format!("serial:{}:{}", self.tnc_host, self.tnc_port),
+            LinkType::Simulated => format!("sim:{}:{}", self.tnc_port, self.tnc_host),
+        }
     }
 
     pub fn get_position_update_time(&self) -> u64 {
         self.position_update_time.parse().unwrap_or(30)
     }
+
+    /// Modem bit rate and TXDELAY in milliseconds
+    pub fn get_modem_timing(&self) -> Result<(u32, u32), String> {
+        let bit_rate = self
+            .bit_rate
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|&bps| bps > 0)
+            .ok_or_else(|| format!("Invalid bit rate: '{}'", self.bit_rate))?;
+        let txdelay_ms = self
+            .txdelay_ms
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| format!("Invalid TXDELAY: '{}'", self.txdelay_ms))?;
+        Ok((bit_rate, txdelay_ms))
+    }
+
+    /// PACLEN in bytes, kept within PACLEN_RANGE
+    pub fn get_paclen(&self) -> usize {
+        self.paclen
+            .parse::<usize>()
+            .unwrap_or(128)
+            .clamp(*PACLEN_RANGE.start(), *PACLEN_RANGE.end())
+    }
+
+    /// Maximum duty cycle as a share of the channel time (0.0 - 1.0), where 1.0
+    /// is no limit. Anything but a percentage above 0 and up to 100 is refused
+    /// rather than read as no limit.
+    pub fn get_max_duty_cycle(&self) -> Result<f32, String> {
+        match self.max_duty_cycle.trim().parse::<f32>() {
+            Ok(percent) if percent > 0.0 && percent <= 100.0 => Ok(percent / 100.0),
+            _ => Err(format!(
+                "Duty cycle must be above 0 and at most 100%, not '{}'",
+                self.max_duty_cycle
+            )),
+        }
+    }
 }
 
 /// System to display the startup menu
 pub fn display_menu(
     mut contexts: EguiContexts,
     mut menu_config: ResMut<MenuConfig>,
+    mut game_config: ResMut<GameConfig>,
 ) {
     let ctx = contexts.ctx_mut();
 
@@ -121,8 +203,8 @@ pub fn display_menu(
                 // Configuration panel - centered
                 ui.allocate_ui_at_rect(
                     egui::Rect::from_center_size(
-                        egui::pos2(center_x, ui.cursor().top() + 150.0),
-                        egui::vec2(500.0, 300.0),
+                        egui::pos2(center_x, ui.cursor().top() + 190.0),
+                        egui::vec2(500.0, 380.0),
                     ),
                     |ui| {
                         egui::Frame::group(ui.style())
@@ -144,6 +226,96 @@ pub fn display_menu(
                                     );
                                     ui.add_space(20.0);
 
+                                    // Station profile picker
+                                    ui.horizontal(|ui| {
+                                        ui.add_sized(
+                                            [180.0, 20.0],
+                                            egui::Label::new(
+                                                egui::RichText::new("Station:")
+                                                    .size(14.0)
+                                                    .color(egui::Color32::from_rgb(120, 200, 120))
+                                                    .family(egui::FontFamily::Monospace),
+                                            ),
+                                        );
+                                        let mut selected = None;
+                                        egui::ComboBox::from_id_salt("station_profile")
+                                            .width(240.0)
+                                            .selected_text(
+                                                egui::RichText::new(&game_config.active)
+                                                    .family(egui::FontFamily::Monospace),
+                                            )
+                                            .show_ui(ui, |ui| {
+                                                for (name, _) in &game_config.stations {
+                                                    if ui
+                                                        .selectable_label(
+                                                            *name == game_config.active,
+                                                            egui::RichText::new(name)
+                                                                .family(egui::FontFamily::Monospace),
+                                                        )
+                                                        .clicked()
+                                                    {
+                                                        selected = Some(name.clone());
+                                                    }
+                                                }
+                                            });
+                                        if let Some(name) = selected {
+                                            if let Some(station) = game_config.get(&name) {
+                                                station.apply_to(&mut menu_config);
+                                            }
+                                            menu_config.station_name = name.clone();
+                                            game_config.active = name;
+                                        }
+                                    });
+                                    ui.add_space(12.0);
+
+                                    // Save the fields below under a profile name, or drop a profile
+                                    ui.horizontal(|ui| {
+                                        ui.add_sized(
+                                            [180.0, 20.0],
+                                            egui::Label::new(
+                                                egui::RichText::new("Profile Name:")
+                                                    .size(14.0)
+                                                    .color(egui::Color32::from_rgb(120, 200, 120))
+                                                    .family(egui::FontFamily::Monospace),
+                                            ),
+                                        );
+                                        ui.add_sized(
+                                            [120.0, 25.0],
+                                            egui::TextEdit::singleline(&mut menu_config.station_name)
+                                                .hint_text("Home HF")
+                                                .font(egui::TextStyle::Monospace),
+                                        );
+                                        let name = menu_config.station_name.trim().to_string();
+                                        if ui
+                                            .add_enabled(
+                                                !name.is_empty(),
+                                                egui::Button::new(
+                                                    egui::RichText::new("SAVE").family(egui::FontFamily::Monospace),
+                                                ),
+                                            )
+                                            .clicked()
+                                        {
+                                            game_config.set(&name, StationProfile::from_menu(&menu_config));
+                                            menu_config.connection_error = game_config.save().err();
+                                        }
+                                        if ui
+                                            .add_enabled(
+                                                game_config.stations.len() > 1 && game_config.get(&name).is_some(),
+                                                egui::Button::new(
+                                                    egui::RichText::new("DELETE").family(egui::FontFamily::Monospace),
+                                                ),
+                                            )
+                                            .clicked()
+                                        {
+                                            game_config.remove(&name);
+                                            let active = game_config.active.clone();
+                                            game_config.active_station().apply_to(&mut menu_config);
+                                            menu_config.station_name = active;
+                                            menu_config.connection_error = game_config.save().err();
+                                        }
+                                    });
+                                    ui.add_space(12.0);
+
                                     // Callsign input
                                     ui.horizontal(|ui| {
                                         ui.add_sized(
@@ -164,12 +336,67 @@ pub fn display_menu(
                                     });
                                     ui.add_space(12.0);
 
+                                    // Profile line sent when saying hello on frequency
+                                    ui.horizontal(|ui| {
+                                        ui.add_sized(
+                                            [180.0, 20.0],
+                                            egui::Label::new(
+                                                egui::RichText::new("Profile:")
+                                                    .size(14.0)
+                                                    .color(egui::Color32::from_rgb(120, 200, 120))
+                                                    .family(egui::FontFamily::Monospace),
+                                            ),
+                                        );
+                                        ui.add_sized(
+                                            [250.0, 25.0],
+                                            egui::TextEdit::singleline(&mut menu_config.profile)
+                                                .hint_text("Optional, e.g. FT-891, 40m dipole")
+                                                .font(egui::TextStyle::Monospace),
+                                        );
+                                    });
+                                    ui.add_space(12.0);
+
+                                    // Link type selector
+                                    ui.horizontal(|ui| {
+                                        ui.add_sized(
+                                            [180.0, 20.0],
+                                            egui::Label::new(
+                                                egui::RichText::new("Link:")
+                                                    .size(14.0)
+                                                    .color(egui::Color32::from_rgb(120, 200, 120))
+                                                    .family(egui::FontFamily::Monospace),
+                                            ),
+                                        );
+                                        ui.selectable_value(
+                                            &mut menu_config.link_type,
+                                            LinkType::TcpKiss,
+                                            egui::RichText::new("TCP KISS").family(egui::FontFamily::Monospace),
+                                        );
+                                        ui.selectable_value(
+                                            &mut menu_config.link_type,
+                                            LinkType::SerialKiss,
+                                            egui::RichText::new("Serial KISS").family(egui::FontFamily::Monospace),
+                                        );
+                                        ui.selectable_value(
+                                            &mut menu_config.link_type,
+                                            LinkType::Simulated,
+                                            egui::RichText::new("Simulated").family(egui::FontFamily::Monospace),
+                                        );
+                                    });
+                                    ui.add_space(12.0);
+
+                                    let (host_label, host_hint, port_label, port_hint) = match menu_config.link_type {
+                                        LinkType::TcpKiss => ("TNC Host:", "127.0.0.1", "TNC Port:", "8100"),
+                                        LinkType::SerialKiss => ("Serial Device:", "/dev/ttyUSB0", "Baud Rate:", "9600"),
+                                        LinkType::Simulated => ("Channel:", "local:300:10", "Bit Rate:", "1200"),
+                                    };
+
                                     // TNC Host input
                                     ui.horizontal(|ui| {
                                         ui.add_sized(
                                             [180.0, 20.0],
                                             egui::Label::new(
-                                                egui::RichText::new("TNC Host:")
+                                                egui::RichText::new(host_label)
                                                     .size(14.0)
                                                     .color(egui::Color32::from_rgb(120, 200, 120))
                                                     .family(egui::FontFamily::Monospace),
@@ -178,7 +405,7 @@ pub fn display_menu(
                                         ui.add_sized(
                                             [250.0, 25.0],
                                             egui::TextEdit::singleline(&mut menu_config.tnc_host)
-                                                .hint_text("127.0.0.1")
+                                                .hint_text(host_hint)
                                                 .font(egui::TextStyle::Monospace),
                                         );
                                     });
@@ -189,7 +416,7 @@ pub fn display_menu(
                                         ui.add_sized(
                                             [180.0, 20.0],
                                             egui::Label::new(
-                                                egui::RichText::new("TNC Port:")
+                                                egui::RichText::new(port_label)
                                                     .size(14.0)
                                                     .color(egui::Color32::from_rgb(120, 200, 120))
                                                     .family(egui::FontFamily::Monospace),
@@ -198,7 +425,7 @@ pub fn display_menu(
                                         ui.add_sized(
                                             [250.0, 25.0],
                                             egui::TextEdit::singleline(&mut menu_config.tnc_port)
-                                                .hint_text("8100")
+                                                .hint_text(port_hint)
                                                 .font(egui::TextStyle::Monospace),
                                         );
                                     });
@@ -224,6 +451,67 @@ pub fn display_menu(
                                     });
                                     ui.add_space(12.0);
 
+                                    // Modem timing and transmit limit for the airtime budget
+                                    ui.horizontal(|ui| {
+                                        ui.add_sized(
+                                            [180.0, 20.0],
+                                            egui::Label::new(
+                                                egui::RichText::new("bps/TXD ms/Duty %:")
+                                                    .size(14.0)
+                                                    .color(egui::Color32::from_rgb(120, 200, 120))
+                                                    .family(egui::FontFamily::Monospace),
+                                            ),
+                                        );
+                                        ui.add_sized(
+                                            [78.0, 25.0],
+                                            egui::TextEdit::singleline(&mut menu_config.bit_rate)
+                                                .hint_text("1200")
+                                                .font(egui::TextStyle::Monospace),
+                                        );
+                                        ui.add_sized(
+                                            [78.0, 25.0],
+                                            egui::TextEdit::singleline(&mut menu_config.txdelay_ms)
+                                                .hint_text("300")
+                                                .font(egui::TextStyle::Monospace),
+                                        );
+                                        ui.add_sized(
+                                            [78.0, 25.0],
+                                            egui::TextEdit::singleline(&mut menu_config.max_duty_cycle)
+                                                .hint_text("100")
+                                                .font(egui::TextStyle::Monospace),
+                                        );
+                                    });
+                                    ui.add_space(12.0);
+
+                                    // Longest frame the modem accepts
+                                    ui.horizontal(|ui| {
+                                        ui.add_sized(
+                                            [180.0, 20.0],
+                                            egui::Label::new(
+                                                egui::RichText::new("PACLEN (bytes):")
+                                                    .size(14.0)
+                                                    .color(egui::Color32::from_rgb(120, 200, 120))
+                                                    .family(egui::FontFamily::Monospace),
+                                            ),
+                                        );
+                                        ui.add_sized(
+                                            [250.0, 25.0],
+                                            egui::TextEdit::singleline(&mut menu_config.paclen)
+                                                .hint_text("128")
+                                                .font(egui::TextStyle::Monospace),
+                                        );
+                                    });
+                                    ui.add_space(12.0);
+
+                                    // Receive-only mode for unlicensed listeners
+                                    ui.checkbox(
+                                        &mut menu_config.listen_only,
+                                        egui::RichText::new("Listen only (never transmit, no callsign needed)")
+                                            .size(14.0)
+                                            .color(egui::Color32::from_rgb(120, 200, 120))
+                                            .family(egui::FontFamily::Monospace),
+                                    );
+
                                     ui.add_space(20.0);
 
                                     // Error message display
@@ -238,15 +526,13 @@ pub fn display_menu(
                                     }
 
                                     // Connect button
-                                    let button_text = if menu_config.validation_time.is_some() {
-                                        "[ VALIDATED - STARTING... ]"
-                                    } else if menu_config.is_connecting {
+                                    let button_text = if menu_config.is_connecting {
                                         "[ CONNECTING... ]"
                                     } else {
                                         "[ CONNECT ]"
                                     };
 
-                                    let button_enabled = !menu_config.is_connecting && menu_config.validation_time.is_none();
+                                    let button_enabled = !menu_config.is_connecting;
 
                                     if ui
                                         .add_enabled(
@@ -268,26 +554,53 @@ pub fn display_menu(
                                         .clicked()
                                     {
                                         // Validate inputs
-                                        let valid = !menu_config.callsign.is_empty()
+                                        let valid = (menu_config.listen_only || !menu_config.callsign.is_empty())
                                             && !menu_config.tnc_host.is_empty()
                                             && !menu_config.tnc_port.is_empty()
                                             && !menu_config.position_update_time.is_empty();
 
-                                        if valid {
-                                            println!("[i] Connecting with callsign: {}", menu_config.callsign);
-                                            println!(
-                                                "[i] TNC: TCP KISS {}:{}",
-                                                menu_config.tnc_host, menu_config.tnc_port
-                                            );
+                                        // Keep the callsign only as other stations will see it,
+                                        // so saves and beacon slots don't depend on how it was typed
+                                        menu_config.callsign = normalize_callsign(&menu_config.callsign);
+                                        let callsign_check = match menu_config.listen_only {
+                                            true => Ok(()),
+                                            false => parse_callsign(&menu_config.callsign).map(drop),
+                                        };
+
+                                        // Modem and duty cycle values have no safe fallback
+                                        let station_settings = callsign_check
+                                            .and(menu_config.get_modem_timing())
+                                            .and(menu_config.get_max_duty_cycle());
+
+                                        if !valid {
+                                            menu_config.connection_error = Some("Please fill in all fields".to_string());
+                                        } else if let Err(e) = station_settings {
+                                            menu_config.connection_error = Some(e);
+                                        } else {
+                                            if menu_config.listen_only {
+                                                println!("[i] Connecting in listen-only mode");
+                                            } else {
+                                                println!("[i] Connecting with callsign: {}", menu_config.callsign);
+                                            }
+                                            println!("[i] TNC: {}", menu_config.get_tnc_address());
                                             println!(
                                                 "[i] Position update interval: {} seconds",
                                                 menu_config.position_update_time
                                             );
                                             menu_config.connection_error = None;
+
+                                            // Remember these settings for next time
+                                            let name = match menu_config.station_name.trim() {
+                                                "" => DEFAULT_PROFILE.to_string(),
+                                                name => name.to_string(),
+                                            };
+                                            game_config.set(&name, StationProfile::from_menu(&menu_config));
+                                            if let Err(e) = game_config.save() {
+                                                println!("[!] {}", e);
+                                            }
+
                                             menu_config.is_connecting = true;
                                             menu_config.connect_clicked = true;
-                                        } else {
-                                            menu_config.connection_error = Some("Please fill in all fields".to_string());
                                         }
                                     }
                                 });
@@ -322,26 +635,14 @@ pub fn display_menu(
         });
 }
 
-/// System to validate TNC connection before entering game
+/// System to validate TNC connection before entering game.
+/// The connection is kept in `TncConnection` for the TNC plugin to use.
 pub fn validate_connection(
     mut menu_config: ResMut<MenuConfig>,
+    mut tnc_connection: ResMut<TncConnection>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
-    use ax25_tnc::tnc::{Tnc, TncAddress};
-    use std::time::{Duration, Instant};
-
-    // Check if we're waiting after successful validation
-    if let Some(validation_time) = menu_config.validation_time {
-        // Wait 3 seconds before transitioning to give modem time to disconnect
-        if validation_time.elapsed() >= Duration::from_secs(3) {
-            println!("[i] Connecting to game...");
-            menu_config.validation_time = None;
-            menu_config.is_connecting = false;
-            menu_config.connect_clicked = false;
-            next_state.set(AppState::InGame);
-        }
-        return;
-    }
+    use crate::connection::transport::open_transport;
 
     // If not validating, return early
     if !menu_config.connect_clicked || !menu_config.is_connecting {
@@ -351,28 +652,18 @@ pub fn validate_connection(
     // Try to connect to the TNC
     let tnc_address_str = menu_config.get_tnc_address();
 
-    match tnc_address_str.parse::<TncAddress>() {
-        Ok(addr) => {
-            match Tnc::open(&addr) {
-                Ok(_tnc) => {
-                    // Connection successful! Set validation time and wait
-                    println!("[i] TNC connection validated successfully");
-                    println!("[i] Waiting for modem to reset...");
-                    menu_config.validation_time = Some(Instant::now());
-                    // Don't transition yet - wait for the timer
-                }
-                Err(e) => {
-                    // Connection failed
-                    let error_msg = format!("Connection failed: {}", e);
-                    println!("[!] {}", error_msg);
-                    menu_config.connection_error = Some(error_msg);
-                    menu_config.is_connecting = false;
-                    menu_config.connect_clicked = false;
-                }
-            }
+    match open_transport(&tnc_address_str) {
+        Ok(transport) => {
+            println!("[i] TNC connection validated successfully");
+            println!("[i] Connecting to game...");
+            tnc_connection.0 = Some(transport);
+            menu_config.is_connecting = false;
+            menu_config.connect_clicked = false;
+            next_state.set(AppState::InGame);
         }
         Err(e) => {
-            let error_msg = format!("Invalid TNC address: {}", e);
+            // Connection failed
+            let error_msg = format!("Connection failed: {}", e);
             println!("[!] {}", error_msg);
             menu_config.connection_error = Some(error_msg);
             menu_config.is_connecting = false;
@@ -1,11 +1,13 @@
 use bevy::audio::*;
 use bevy::asset::AssetServer;
 use bevy::ecs::system::{Commands, Res, ResMut};
+use crate::config::Settings;
 
 pub fn play_background_audio(
   asset_server: Res<AssetServer>,
   mut commands: Commands,
   mut volume: ResMut<GlobalVolume>,
+  settings: Res<Settings>,
 ) {
   let audio = asset_server.load("background_audio.ogg");
 
@@ -14,5 +16,5 @@ pub fn play_background_audio(
     AudioPlayer::new(audio),
     PlaybackSettings::LOOP,
   ));
-  volume.volume = Volume::Linear(0.1);
+  volume.volume = Volume::Linear(settings.volume);
 }
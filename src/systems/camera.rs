@@ -1,6 +1,12 @@
 use crate::components::Entity;
-use crate::constants::CAMERA_DECAY_RATE;
+use crate::config::Settings;
+use crate::connection::resources::ListenOnly;
+use crate::constants::{CAMERA_DECAY_RATE, SPECTATOR_SPEED};
+use crate::systems::gui::ChatInputState;
+use crate::systems::settings::SettingsWindowState;
+use crate::systems::world_map::WorldMap;
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
 
 //Updates every frame to follow the player entity with a smooth camera effect.
 pub fn update_camera(
@@ -17,3 +23,55 @@ pub fn update_camera(
         .translation
         .smooth_nudge(&direction, CAMERA_DECAY_RATE, time.delta_secs());
 }
+
+// Free-flying spectator camera for listen-only mode, where there is no local player.
+pub fn spectator_camera(
+    mut camera: Single<&mut Transform, With<Camera2d>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    chat_state: Res<ChatInputState>,
+    settings_window: Res<SettingsWindowState>,
+    settings: Res<Settings>,
+    listen_only: Res<ListenOnly>,
+    time: Res<Time>,
+) {
+    if !listen_only.0 || chat_state.active || settings_window.open {
+        return;
+    }
+    let keys = &settings.keys;
+
+    let mut direction = Vec2::ZERO;
+    if keyboard.pressed(keys.left) {
+        direction.x -= 1.0;
+    }
+    if keyboard.pressed(keys.right) {
+        direction.x += 1.0;
+    }
+    if keyboard.pressed(keys.up) {
+        direction.y += 1.0;
+    }
+    if keyboard.pressed(keys.down) {
+        direction.y -= 1.0;
+    }
+    camera.translation +=
+        (direction.normalize_or_zero() * SPECTATOR_SPEED * time.delta_secs()).extend(0.0);
+}
+
+// Keep the view on the map. On an axis where the map is smaller than the view,
+// the map is centered.
+pub fn clamp_camera(
+    mut camera: Single<(&mut Transform, &Projection), With<Camera2d>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    world_map: Res<WorldMap>,
+) {
+    let (transform, projection) = &mut *camera;
+    let Projection::Orthographic(orthographic) = projection else {
+        return;
+    };
+    let half_view = window.size() * orthographic.scale / 2.0;
+    let position = transform.translation.truncate();
+    let clamped = world_map.clamp(position, half_view);
+    if clamped != position {
+        transform.translation.x = clamped.x;
+        transform.translation.y = clamped.y;
+    }
+}
@@ -1,29 +1,62 @@
 use crate::components::RemotePlayer;
-use crate::connection::compression::encode_chat;
+use crate::config::{Settings, key_name};
+use crate::connection::callsign::{normalize_callsign, parse_callsign};
+use crate::connection::compression::{can_acknowledge, encode_chat_frames};
+use crate::connection::delivery::ChatDelivery;
 use crate::connection::message::MessageType;
-use crate::connection::tnc_plugin::TncOutgoingEvent;
-use crate::connection::tnc_integration::GameState;
+use crate::connection::reassembly::ChatReassembly;
+use crate::connection::resources::{ListenOnly, Paclen};
+use crate::connection::scheduler::DUTY_CYCLE_WINDOW;
+use crate::connection::tnc_plugin::{LinkStatus, TncChannels, TncOutgoingEvent};
+use crate::connection::tnc_integration::{GameState, add_chat_line};
+use crate::systems::settings::SettingsWindowState;
+use crate::systems::world_map::{WorldMap, zone_name};
 use bevy::prelude::*;
 use bevy_egui::{EguiContexts, egui};
 
 // Resource to track chat input state
 //This helps to see if the player is chatting or not.
-#[derive(Resource, Default)]
+#[derive(Resource)]
 pub struct ChatInputState {
     pub active: bool,
     pub input: String,
+    // ID for the next message sent, so receivers can put its parts together
+    // and spot retries
+    pub next_message_id: u8,
+    // Conversation shown: None for the main chat, or a station's callsign
+    pub tab: Option<String>,
+}
+
+impl Default for ChatInputState {
+    fn default() -> Self {
+        Self {
+            active: false,
+            input: String::new(),
+            // Start somewhere random so a restart doesn't reuse IDs that
+            // receivers still remember
+            next_message_id: rand::random(),
+            tab: None,
+        }
+    }
 }
 
 // Chat window system
+#[allow(clippy::too_many_arguments)]
 pub fn chat_window(
     mut contexts: EguiContexts,
     mut chat_state: ResMut<ChatInputState>,
     keyboard: Res<ButtonInput<KeyCode>>,
     mut event_writer: EventWriter<TncOutgoingEvent>,
     mut game_state: ResMut<GameState>,
+    listen_only: Res<ListenOnly>,
+    settings: Res<Settings>,
+    settings_window: Res<SettingsWindowState>,
+    paclen: Res<Paclen>,
+    reassembly: Res<ChatReassembly>,
+    mut delivery: ResMut<ChatDelivery>,
 ) {
-    // Toggle chat input with T key
-    if keyboard.just_pressed(KeyCode::KeyT) {
+    // Toggle chat input with the chat key (listeners cannot transmit)
+    if keyboard.just_pressed(settings.keys.chat) && !listen_only.0 && !settings_window.open {
         chat_state.active = true;
     }
 
@@ -50,12 +83,38 @@ pub fn chat_window(
         .show(ctx, |ui| {
             // Chat history area
             ui.add_space(100.0);
+
+            // One tab per direct message conversation, with its unread count
+            if !game_state.direct_messages.is_empty() {
+                ui.horizontal_wrapped(|ui| {
+                    if ui.selectable_label(chat_state.tab.is_none(), "All").clicked() {
+                        chat_state.tab = None;
+                    }
+                    for callsign in game_state.direct_messages.keys() {
+                        let text = match game_state.unread.get(callsign) {
+                            Some(&unread) if unread > 0 => format!("{} ({})", callsign, unread),
+                            _ => callsign.clone(),
+                        };
+                        let selected = chat_state.tab.as_ref() == Some(callsign);
+                        if ui.selectable_label(selected, text).clicked() {
+                            chat_state.tab = Some(callsign.clone());
+                        }
+                    }
+                });
+                ui.separator();
+            }
+            // The conversation on screen has been read
+            if let Some(callsign) = &chat_state.tab {
+                game_state.unread.remove(callsign);
+            }
+
             egui::ScrollArea::vertical()
                 .max_height(ui.available_height() - 25.0) // Adjust dynamically to window size
                 .stick_to_bottom(true)
                 .show(ui, |ui| {
                     // Display the recent messages
-                    for message in game_state.chat_messages.iter().rev().take(20).rev() {
+                    let messages = game_state.chat_lines(chat_state.tab.as_deref());
+                    for message in messages.iter().rev().take(20).rev() {
                         ui.label(
                             egui::RichText::new(message)
                                 .size(18.0) // Larger font size for messages
@@ -63,6 +122,19 @@ pub fn chat_window(
                         );
                         ui.separator();
                     }
+
+                    // Long messages still coming in
+                    for (callsign, received, count) in reassembly.in_progress() {
+                        ui.label(
+                            egui::RichText::new(format!(
+                                "{} is sending a long message ({}/{} parts)...",
+                                callsign, received, count
+                            ))
+                            .size(16.0)
+                            .italics()
+                            .color(egui::Color32::from_rgba_unmultiplied(200, 200, 200, 200)),
+                        );
+                    }
                 });
 
             // Chat input area - only shown when active
@@ -70,8 +142,12 @@ pub fn chat_window(
                 ui.add_space(10.0);
                 ui.horizontal(|ui| {
                     // Text input field with focus
+                    let hint = match &chat_state.tab {
+                        Some(callsign) => format!("Message {} and press Enter...", callsign),
+                        None => "Type message and press Enter...".to_string(),
+                    };
                     let text_edit = egui::TextEdit::singleline(&mut chat_state.input)
-                        .hint_text("Type message and press Enter...")
+                        .hint_text(hint)
                         .desired_width(ui.available_width() - 80.0)
                         .font(egui::TextStyle::Heading) // Use heading style for larger text
                         .text_color(egui::Color32::WHITE);
@@ -91,23 +167,73 @@ pub fn chat_window(
                         .clicked();
                     let enter_pressed = ui.input(|i| i.key_pressed(egui::Key::Enter));
 
-                    if (enter_pressed || send_pressed) && !chat_state.input.trim().is_empty() {
-                        // Send the message using custom compact protocol
-                        let encoded_chat = encode_chat(
+                    let submitted =
+                        (enter_pressed || send_pressed) && !chat_state.input.trim().is_empty();
+                    let parsed = submitted
+                        .then(|| parse_chat_input(&chat_state.input, chat_state.tab.as_deref()));
+                    let outgoing = match parsed {
+                        None => None,
+                        Some(Err(e)) => {
+                            let tab = chat_state.tab.clone();
+                            add_chat_line(&mut game_state, tab.as_deref(), format!("* {}", e));
+                            None
+                        }
+                        // "/msg CALL" alone just opens the conversation
+                        Some(Ok((Some(callsign), text))) if text.is_empty() => {
+                            game_state.chat_lines_mut(Some(&callsign));
+                            chat_state.tab = Some(callsign);
+                            chat_state.input.clear();
+                            None
+                        }
+                        Some(Ok(message)) => Some(message),
+                    };
+
+                    if let Some((destination, text)) = outgoing {
+                        // Send the message using custom compact protocol, in
+                        // parts if it is longer than a frame. Direct messages
+                        // always ask for an ACK.
+                        let id = chat_state.next_message_id;
+                        let ack = (settings.ack_chat || destination.is_some())
+                            && can_acknowledge(&game_state.player_callsign);
+                        let frames = encode_chat_frames(
                             &game_state.player_callsign,
-                            &chat_state.input
+                            id,
+                            ack,
+                            &text,
+                            paclen.0,
                         );
-
-                        // Add to local chat history
-                        let player_message =
-                            format!("{}: {}", game_state.player_callsign, chat_state.input);
-                        game_state.chat_messages.push(player_message);
+                        let message_type = if frames.len() > 1 {
+                            MessageType::ChatFragment
+                        } else {
+                            MessageType::Chat
+                        };
+                        chat_state.next_message_id = chat_state.next_message_id.wrapping_add(1);
 
                         // Send over TNC
-                        event_writer.write(TncOutgoingEvent {
-                            message: encoded_chat,
-                            message_type: MessageType::Chat,
-                        });
+                        for message in &frames {
+                            event_writer.write(TncOutgoingEvent {
+                                message: message.clone(),
+                                message_type: message_type.clone(),
+                                destination: destination.clone(),
+                            });
+                        }
+
+                        // Add to local chat history, marked until it is
+                        // acknowledged if we asked for that
+                        let mut player_message = format!("{}: {}", game_state.player_callsign, text);
+                        if ack {
+                            player_message = delivery.track(
+                                id,
+                                frames,
+                                message_type,
+                                destination.clone(),
+                                player_message,
+                            );
+                        }
+                        game_state
+                            .chat_lines_mut(destination.as_deref())
+                            .push(player_message);
+                        chat_state.tab = destination;
 
                         // Clear the input and disable chat mode
                         chat_state.input.clear();
@@ -119,10 +245,18 @@ pub fn chat_window(
                         chat_state.active = false;
                     }
                 });
+            } else if listen_only.0 {
+                ui.add_space(5.0);
+                ui.label(
+                    egui::RichText::new("Listening only - transmit disabled")
+                        .size(16.0)
+                        .italics()
+                        .color(egui::Color32::from_rgba_unmultiplied(200, 200, 200, 200)),
+                );
             } else {
                 ui.add_space(5.0);
                 ui.label(
-                    egui::RichText::new("Press T to chat")
+                    egui::RichText::new(format!("Press {} to chat", key_name(settings.keys.chat)))
                         .size(16.0)
                         .italics()
                         .color(egui::Color32::from_rgba_unmultiplied(200, 200, 200, 200)),
@@ -131,13 +265,178 @@ pub fn chat_window(
         });
 }
 
+// Work out who typed chat goes to, and what it says. "/msg CALL text" goes to
+// CALL, anything else to whoever the open tab is for (None for everyone).
+fn parse_chat_input(input: &str, tab: Option<&str>) -> Result<(Option<String>, String), String> {
+    let Some(rest) = input.strip_prefix("/msg ") else {
+        return Ok((tab.map(str::to_string), input.to_string()));
+    };
+    let rest = rest.trim_start();
+    let (callsign, text) = rest.split_once(' ').unwrap_or((rest, ""));
+    parse_callsign(callsign)?;
+    Ok((Some(normalize_callsign(callsign)), text.trim().to_string()))
+}
+
+// Link status HUD shown next to the chat window
+pub fn link_status_hud(
+    mut contexts: EguiContexts,
+    link_status: Res<LinkStatus>,
+    tnc_channels: Option<Res<TncChannels>>,
+) {
+    let ctx = contexts.ctx_mut();
+
+    let (text, color) = match &*link_status {
+        LinkStatus::Connecting => (
+            "TNC: CONNECTING...".to_string(),
+            egui::Color32::from_rgb(200, 200, 100),
+        ),
+        LinkStatus::Connected => (
+            "TNC: CONNECTED".to_string(),
+            egui::Color32::from_rgb(100, 200, 100),
+        ),
+        LinkStatus::Reconnecting {
+            attempt,
+            next_attempt,
+            last_error,
+        } => (
+            format!(
+                "TNC: RECONNECTING (attempt {}, retry in {}s)\n{}",
+                attempt,
+                next_attempt
+                    .saturating_duration_since(std::time::Instant::now())
+                    .as_secs(),
+                last_error
+            ),
+            egui::Color32::from_rgb(230, 160, 60),
+        ),
+        LinkStatus::Failed { last_error } => (
+            format!("TNC: FAILED\n{}", last_error),
+            egui::Color32::from_rgb(255, 100, 100),
+        ),
+    };
+
+    egui::Window::new("Link")
+        .frame(egui::Frame {
+            fill: egui::Color32::from_rgba_unmultiplied(20, 20, 20, 220),
+            stroke: egui::Stroke::new(
+                1.0,
+                egui::Color32::from_rgba_unmultiplied(100, 100, 100, 200),
+            ),
+            inner_margin: egui::Margin::same(6),
+            ..Default::default()
+        })
+        .title_bar(false)
+        .resizable(false)
+        .anchor(egui::Align2::LEFT_BOTTOM, egui::Vec2::new(410.0, -25.0))
+        .show(ctx, |ui| {
+            ui.label(egui::RichText::new(text).size(14.0).color(color));
+
+            // Frames still waiting for the channel, and our share of the airtime
+            if let Some(tnc_channels) = &tnc_channels {
+                ui.label(
+                    egui::RichText::new(format!("Queued: {}", tnc_channels.queue_depth()))
+                        .size(12.0)
+                        .color(egui::Color32::from_rgb(180, 180, 180)),
+                );
+
+                let (usage, max) = tnc_channels.airtime_usage();
+                let limit = if max >= 1.0 {
+                    "no limit".to_string()
+                } else {
+                    format!("limit {:.0}%", max * 100.0)
+                };
+                // Positions are held back from 80% of the limit
+                let color = if max < 1.0 && usage >= max * 0.8 {
+                    egui::Color32::from_rgb(230, 160, 60)
+                } else {
+                    egui::Color32::from_rgb(180, 180, 180)
+                };
+                ui.label(
+                    egui::RichText::new(format!(
+                        "Airtime ({} min): {:.1}% ({})",
+                        DUTY_CYCLE_WINDOW.as_secs() / 60,
+                        usage * 100.0,
+                        limit
+                    ))
+                    .size(12.0)
+                    .color(color),
+                );
+            }
+
+            // Let the player retry right away instead of waiting out the backoff
+            if !matches!(*link_status, LinkStatus::Connected | LinkStatus::Connecting) {
+                if let Some(tnc_channels) = &tnc_channels {
+                    if ui.button("Reconnect now").clicked() {
+                        let _ = tnc_channels.retry.send(());
+                    }
+                }
+            }
+        });
+}
+
+// Roster of every station heard, and which map each one is on
+pub fn roster_window(
+    mut contexts: EguiContexts,
+    game_state: Res<GameState>,
+    world_map: Res<WorldMap>,
+) {
+    let ctx = contexts.ctx_mut();
+
+    egui::Window::new("Stations")
+        .frame(egui::Frame {
+            fill: egui::Color32::from_rgba_unmultiplied(20, 20, 20, 220),
+            stroke: egui::Stroke::new(
+                1.0,
+                egui::Color32::from_rgba_unmultiplied(100, 100, 100, 200),
+            ),
+            inner_margin: egui::Margin::same(6),
+            ..Default::default()
+        })
+        .resizable(false)
+        .collapsible(true)
+        .default_open(true)
+        .anchor(egui::Align2::RIGHT_TOP, egui::Vec2::new(-10.0, 10.0))
+        .show(ctx, |ui| {
+            if game_state.known_players.is_empty() {
+                ui.label(
+                    egui::RichText::new("Nobody heard yet")
+                        .size(14.0)
+                        .italics()
+                        .color(egui::Color32::from_rgba_unmultiplied(200, 200, 200, 200)),
+                );
+            }
+            for callsign in &game_state.known_players {
+                let (whereabouts, color) = match game_state.last_fixes.get(callsign) {
+                    Some(fix) if fix.zone == world_map.zone => {
+                        ("here".to_string(), egui::Color32::from_rgb(100, 200, 100))
+                    }
+                    Some(fix) => (zone_name(fix.zone), egui::Color32::from_rgb(200, 200, 100)),
+                    None => (
+                        "no position yet".to_string(),
+                        egui::Color32::from_rgb(160, 160, 160),
+                    ),
+                };
+                ui.label(
+                    egui::RichText::new(format!("{} - {}", callsign, whereabouts))
+                        .size(14.0)
+                        .color(color),
+                );
+            }
+        });
+}
+
 //This is updated every frame to display each player callsign above their head.
+//Clicking another player's callsign opens a direct message conversation with them.
+#[allow(clippy::too_many_arguments)]
 pub fn display_player_callsigns(
     mut contexts: EguiContexts,
     cameras: Query<(&Camera, &GlobalTransform)>,
     remote_players: Query<(&Transform, &RemotePlayer)>,
     local_player: Query<&Transform, (With<crate::components::Entity>, Without<RemotePlayer>)>,
-    game_state: Res<GameState>,
+    mut game_state: ResMut<GameState>,
+    mut chat_state: ResMut<ChatInputState>,
+    listen_only: Res<ListenOnly>,
+    settings_window: Res<SettingsWindowState>,
 ) {
     // Get the camera for screen position calculations
     let (camera, camera_transform) = match cameras.single() {
@@ -162,13 +461,24 @@ pub fn display_player_callsigns(
                 // Convert world position to screen position
                 if let Some(screen_pos) = world_to_screen(camera, camera_transform, label_world_pos)
                 {
-                    ui.painter().text(
+                    let rect = ui.painter().text(
                         egui::pos2(screen_pos.x, screen_pos.y),
                         egui::Align2::CENTER_CENTER,
                         &remote_player.callsign,
                         egui::FontId::proportional(14.0),
                         egui::Color32::WHITE,
                     );
+                    let id = egui::Id::new(("player_label", &remote_player.callsign));
+                    let response = ui
+                        .interact(rect, id, egui::Sense::click())
+                        .on_hover_cursor(egui::CursorIcon::PointingHand);
+                    if response.clicked() && !settings_window.open {
+                        let callsign = normalize_callsign(&remote_player.callsign);
+                        game_state.chat_lines_mut(Some(&callsign));
+                        chat_state.tab = Some(callsign);
+                        // Listeners can read the conversation but not reply
+                        chat_state.active = !listen_only.0;
+                    }
                 }
             }
 
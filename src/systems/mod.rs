@@ -3,5 +3,9 @@ pub mod camera;
 pub mod gui;
 pub mod player;
 pub mod remote_player;
+pub mod save;
+pub mod settings;
 pub mod setup;
 pub mod audio;
+pub mod world_map;
+pub mod zones;
@@ -1,16 +1,48 @@
+use crate::config::Settings;
 use crate::components::{AnimationIndices, AnimationTimer, Animations, Entity, RemotePlayer};
-use crate::constants::SPEED;
+use crate::connection::resources::ListenOnly;
+use crate::connection::tnc_integration::GameState;
+use crate::constants::{PLAYER_FOOT_OFFSET, SPEED};
+use crate::systems::world_map::{WorldMap, YSort};
 use crate::systems::gui::ChatInputState;
+use crate::systems::save::SaveGame;
+use crate::systems::settings::SettingsWindowState;
 use avian2d::prelude::*;
 use bevy::prelude::*;
 use std::time::Duration;
 
 //Creates a new player entity with animations and physics components.
+//Listen-only stations have no avatar and fly the camera instead.
+//The player comes back where they left this map, or starts at a spawn point.
+#[allow(clippy::too_many_arguments)]
 pub fn add_player(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    listen_only: Res<ListenOnly>,
+    world_map: Res<WorldMap>,
+    save_game: Res<SaveGame>,
+    game_state: Res<GameState>,
+    mut camera: Query<&mut Transform, With<Camera2d>>,
 ) {
+    if listen_only.0 {
+        return;
+    }
+
+    // A remembered spot may be inside something added to the map since
+    let start = save_game
+        .position(&world_map.path)
+        .filter(|&position| {
+            world_map
+                .world_to_cell(position)
+                .is_some_and(|cell| world_map.is_walkable(cell))
+        })
+        .unwrap_or_else(|| world_map.spawn_point(&game_state.player_callsign));
+    if let Ok(mut camera) = camera.single_mut() {
+        camera.translation.x = start.x;
+        camera.translation.y = start.y;
+    }
+
     let texture = asset_server.load("player.png");
     let layout = TextureAtlasLayout::from_grid(UVec2::splat(32), 5, 8, None, None);
     let texture_atlas_layout = texture_atlas_layouts.add(layout);
@@ -50,15 +82,21 @@ pub fn add_player(
                 index: animations.walk_south.first,
             },
         ),
-        Transform::from_translation(Vec3::new(0.0, 0.0, 2.0)),
+        Transform::from_translation(start.extend(2.0)),
         animations,
         AnimationIndices { first: 0, last: 3 },
         AnimationTimer(Timer::from_seconds(0.2, TimerMode::Repeating)),
-        RigidBody::Kinematic,
+        // Dynamic so the solver stops us at the map's static colliders
+        RigidBody::Dynamic,
         Collider::circle(16.0),
+        LockedAxes::ROTATION_LOCKED,
+        Friction::ZERO,
         LinearVelocity(Vec2::ZERO),
         AngularVelocity(0.0),
         GravityScale(0.0),
+        YSort {
+            foot_offset: PLAYER_FOOT_OFFSET,
+        },
         Entity,
     ));
 }
@@ -67,36 +105,39 @@ pub fn add_player(
 pub fn move_player(
     keyboard: Res<ButtonInput<KeyCode>>,
     chat_state: Res<ChatInputState>,
+    settings_window: Res<SettingsWindowState>,
+    settings: Res<Settings>,
     mut player_query: Query<(&mut LinearVelocity, &mut AnimationTimer), Without<RemotePlayer>>,
 ) {
-    // Don't process movement if chat is active
-    if chat_state.active {
+    // Don't process movement if chat or the settings window is open
+    if chat_state.active || settings_window.open {
         return;
     }
 
+    let keys = &settings.keys;
     for (mut linear_velocity, mut animationtimer) in &mut player_query {
         let mut direction = Vec2::ZERO;
-        if keyboard.pressed(KeyCode::KeyA) {
+        if keyboard.pressed(keys.left) {
             direction.x -= 1.0;
-            if keyboard.just_pressed(KeyCode::KeyA) {
+            if keyboard.just_pressed(keys.left) {
                 animationtimer.set_duration(Duration::from_secs_f32(0.15));
             }
         }
-        if keyboard.pressed(KeyCode::KeyD) {
+        if keyboard.pressed(keys.right) {
             direction.x += 1.0;
-            if keyboard.just_pressed(KeyCode::KeyD) {
+            if keyboard.just_pressed(keys.right) {
                 animationtimer.set_duration(Duration::from_secs_f32(0.15));
             }
         }
-        if keyboard.pressed(KeyCode::KeyW) {
+        if keyboard.pressed(keys.up) {
             direction.y += 1.0;
-            if keyboard.just_pressed(KeyCode::KeyW) {
+            if keyboard.just_pressed(keys.up) {
                 animationtimer.set_duration(Duration::from_secs_f32(0.15));
             }
         }
-        if keyboard.pressed(KeyCode::KeyS) {
+        if keyboard.pressed(keys.down) {
             direction.y -= 1.0;
-            if keyboard.just_pressed(KeyCode::KeyS) {
+            if keyboard.just_pressed(keys.down) {
                 animationtimer.set_duration(Duration::from_secs_f32(0.15));
             }
         }
@@ -108,3 +149,20 @@ pub fn move_player(
         linear_velocity.0 = direction * SPEED;
     }
 }
+
+// Keep the player inside the map, even where it has no border walls
+pub fn clamp_player(
+    mut player: Query<&mut Transform, (With<Entity>, Without<RemotePlayer>)>,
+    world_map: Res<WorldMap>,
+) {
+    let Ok(mut transform) = player.single_mut() else {
+        return;
+    };
+    let position = transform.translation.truncate();
+    // Keep the whole collider (radius 16) on the map
+    let clamped = world_map.clamp(position, Vec2::splat(16.0));
+    if clamped != position {
+        transform.translation.x = clamped.x;
+        transform.translation.y = clamped.y;
+    }
+}
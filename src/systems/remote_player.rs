@@ -1,4 +1,9 @@
 use crate::components::{AnimationIndices, AnimationTimer, Animations, RemotePlayer};
+use crate::constants::{
+    PLAYER_FOOT_OFFSET, REMOTE_EXTRAPOLATION_HORIZON, REMOTE_PATH_MAX_DURATION,
+    REMOTE_TELEPORT_FADE, SPEED,
+};
+use crate::systems::world_map::{WorldMap, YSort};
 use crate::connection::tnc_integration::GameState;
 use avian2d::prelude::*;
 use bevy::prelude::*;
@@ -6,41 +11,74 @@ use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant};
 
 //Used to create smooth movement for rmeote players with infrequent updates.
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct PlayerPositionData {
     pub callsign: String,
     pub x: f32,
     pub y: f32,
     pub direction: String,
+    pub moving: bool,
+    // Coarse velocity hint in world units per second
+    pub vx: f32,
+    pub vy: f32,
+    // Which map the player is on
+    pub zone: u8,
+    // Beacon time slot the position was sent in, if any
+    pub slot: Option<u8>,
 }
 
 // System to remove inactive players
-//Players that have not updated in 2 minutes  are removed form the game state.
+//Players that have not been heard from in 2 minutes are removed form the game state.
 pub fn cleanup_inactive_players(
     mut commands: Commands,
     mut game_state: ResMut<GameState>,
     query: Query<(Entity, &RemotePlayer)>,
 ) {
     let now = Instant::now();
-    let timeout = Duration::from_secs(120); // Remove after 1 minute of inactivity
+    let timeout = Duration::from_secs(120); // Remove after 2 minutes of inactivity
 
     for (entity, player) in query.iter() {
-        if now.duration_since(player.last_update) > timeout {
+        // Any packet from the station counts, not just position changes
+        let last_heard = game_state
+            .last_heard
+            .get(&player.callsign)
+            .map_or(player.last_update, |&heard| heard.max(player.last_update));
+        if now.duration_since(last_heard) > timeout {
             // Remove player from game state tracking
             game_state.player_entities.remove(&player.callsign);
             game_state.known_players.retain(|p| p != &player.callsign);
+            game_state.last_heard.remove(&player.callsign);
+            game_state.last_fixes.remove(&player.callsign);
 
             // Despawn the entity
             commands.entity(entity).despawn();
             println!("Player timed out: {}", player.callsign);
         }
     }
+
+    // Stations that said hello but never sent a position, or that are on another
+    // map, have no entity to time out
+    let GameState {
+        known_players,
+        player_entities,
+        last_heard,
+        last_fixes,
+        ..
+    } = &mut *game_state;
+    known_players.retain(|callsign| {
+        player_entities.contains_key(callsign)
+            || last_heard
+                .get(callsign)
+                .is_some_and(|&heard| now.duration_since(heard) <= timeout)
+    });
+    last_fixes.retain(|callsign, _| known_players.contains(callsign));
 }
 
 pub fn spawn_player_remote(
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
     texture_atlas_layouts: &mut ResMut<Assets<TextureAtlasLayout>>,
+    world_map: &WorldMap,
     pos_data: &PlayerPositionData,
 ) -> bevy::prelude::Entity {
     let texture = asset_server.load("player.png");
@@ -75,6 +113,8 @@ pub fn spawn_player_remote(
         },
     };
     let spawn_position = Vec3::new(pos_data.x, pos_data.y, 2.0);
+    let initial_animation = animations.for_state(&pos_data.direction, pos_data.moving);
+    let velocity = Vec2::new(pos_data.vx, pos_data.vy);
 
     // Spawn and return the entity ID
     commands
@@ -83,17 +123,22 @@ pub fn spawn_player_remote(
                 texture,
                 TextureAtlas {
                     layout: texture_atlas_layout,
-                    index: animations.walk_south.first,
+                    index: initial_animation.first,
                 },
             ),
             animations,
-            AnimationIndices { first: 0, last: 3 },
+            initial_animation,
             AnimationTimer(Timer::from_seconds(0.2, TimerMode::Repeating)),
             RigidBody::Kinematic,
             Collider::circle(16.0),
+            // Moved by network updates, so it must not shove the local player
+            Sensor,
             LinearVelocity(Vec2::ZERO),
             AngularVelocity(0.0),
             GravityScale(0.0),
+            YSort {
+                foot_offset: PLAYER_FOOT_OFFSET,
+            },
             Transform::from_translation(Vec3::new(pos_data.x, pos_data.y, 2.0)),
             RemotePlayer {
                 callsign: pos_data.callsign.clone(),
@@ -102,82 +147,201 @@ pub fn spawn_player_remote(
                 start_position: spawn_position,
                 move_start_time: Instant::now(),
                 move_duration: 4.0,
-                is_moving: false,
+                is_moving: velocity != Vec2::ZERO,
+                velocity,
+                extrapolation_time: extrapolation_time(world_map, spawn_position, velocity),
+                facing: pos_data.direction.clone(),
+                reported_moving: pos_data.moving,
+                path: Vec::new(),
+                fade_start: None,
             },
         ))
         .id()
 }
 
+// How long to follow `velocity` from `fix` before hitting the horizon or a wall
+fn extrapolation_time(world_map: &WorldMap, fix: Vec3, velocity: Vec2) -> f32 {
+    let speed = velocity.length();
+    if speed == 0.0 {
+        return 0.0;
+    }
+    let max = speed * REMOTE_EXTRAPOLATION_HORIZON;
+    world_map.clear_distance(fix.truncate(), velocity, max) / speed
+}
+
+// Aim a remote avatar at a new fix: a straight glide if nothing is in the way,
+// otherwise along an A* path around obstacles, otherwise fade out and back in
+// at the fix.
+pub fn retarget_remote_player(
+    remote_player: &mut RemotePlayer,
+    current: Vec3,
+    fix: Vec3,
+    world_map: &WorldMap,
+) {
+    remote_player.start_position = current;
+    remote_player.target_position = fix;
+    remote_player.move_start_time = Instant::now();
+    remote_player.move_duration = 2.0; // 2 seconds to move
+    remote_player.is_moving = true;
+    remote_player.extrapolation_time = extrapolation_time(world_map, fix, remote_player.velocity);
+    remote_player.path.clear();
+    remote_player.fade_start = None;
+
+    let (from, to) = (current.truncate(), fix.truncate());
+    if world_map.world_to_cell(from) == world_map.world_to_cell(to)
+        || world_map.line_is_clear(from, to)
+    {
+        return;
+    }
+
+    match world_map.find_path(from, to) {
+        Some(path) => {
+            let length: f32 = path.windows(2).map(|w| w[0].distance(w[1])).sum();
+            // Walk it at least at normal speed, but don't fall too far behind
+            remote_player.move_duration = (length / SPEED).clamp(2.0, REMOTE_PATH_MAX_DURATION);
+            remote_player.path = path.into_iter().map(|p| p.extend(current.z)).collect();
+        }
+        None => {
+            println!("[i] No path to {}'s new position, teleporting", remote_player.callsign);
+            remote_player.fade_start = Some(Instant::now());
+        }
+    }
+}
+
+// Point `distance` along a polyline, and the direction of the segment it lies on
+fn point_along(path: &[Vec3], mut distance: f32) -> (Vec3, Vec3) {
+    for segment in path.windows(2) {
+        let length = segment[0].distance(segment[1]);
+        if distance <= length && length > 0.0 {
+            return (segment[0].lerp(segment[1], distance / length), segment[1] - segment[0]);
+        }
+        distance -= length;
+    }
+    let n = path.len();
+    (path[n - 1], path[n - 1] - path[n.saturating_sub(2)])
+}
+
 // In tcp_integration.rs or a new file like remote_player.rs
+//
+// Without obstacles, the avatar follows a dead-reckoned track: the last fix plus
+// its velocity hint, for at most REMOTE_EXTRAPOLATION_HORIZON seconds (or until
+// the track runs into a wall). Where the avatar stood when the fix arrived is
+// blended into that track over move_duration, so corrections glide instead of
+// snapping. With obstacles in the way it walks a path to the fix instead, or
+// fades out and back in when there is no path.
 pub fn update_remote_player_movement(
     mut query: Query<(
         &mut Transform,
         &mut RemotePlayer,
         &mut AnimationIndices,
         &Animations,
+        &mut Sprite,
     )>,
 ) {
     let current_time = Instant::now();
 
-    for (mut transform, mut remote_player, mut animation_indices, animations) in query.iter_mut() {
-        if remote_player.is_moving {
-            // Calculate how much time has passed since movement started
-            let elapsed = current_time
-                .duration_since(remote_player.move_start_time)
-                .as_secs_f32();
-            let progress = (elapsed / remote_player.move_duration).clamp(0.0, 1.0);
-
-            // Calculate movement direction for animation
-            let movement_vector = remote_player.target_position - remote_player.start_position;
-
-            // Update animation based on primary movement direction
-            if movement_vector.x.abs() > movement_vector.y.abs() {
-                // Horizontal movement is dominant
-                if movement_vector.x > 0.0 {
-                    // Moving right (east)
-                    *animation_indices = animations.walk_east;
-                } else {
-                    // Moving left (west)
-                    *animation_indices = animations.walk_west;
-                }
-            } else {
-                // Vertical movement is dominant
-                if movement_vector.y > 0.0 {
-                    // Moving up (north)
-                    *animation_indices = animations.walk_north;
-                } else {
-                    // Moving down (south)
-                    *animation_indices = animations.walk_south;
-                }
+    for (mut transform, mut remote_player, mut animation_indices, animations, mut sprite) in
+        query.iter_mut()
+    {
+        if !remote_player.is_moving {
+            continue;
+        }
+
+        // Calculate how much time has passed since the fix arrived
+        let elapsed = current_time
+            .duration_since(remote_player.move_start_time)
+            .as_secs_f32();
+        let progress = (elapsed / remote_player.move_duration).clamp(0.0, 1.0);
+
+        if let Some(fade_start) = remote_player.fade_start {
+            // Fade out where we are, jump, fade back in at the fix
+            let half = REMOTE_TELEPORT_FADE / 2.0;
+            let t = current_time.duration_since(fade_start).as_secs_f32();
+            if t < half {
+                sprite.color = Color::WHITE.with_alpha(1.0 - t / half);
+                continue;
+            }
+            transform.translation = remote_player.target_position;
+            if t < REMOTE_TELEPORT_FADE {
+                sprite.color = Color::WHITE.with_alpha((t - half) / half);
+                continue;
             }
+            sprite.color = Color::WHITE;
+            remote_player.fade_start = None;
+            remote_player.is_moving = false;
+            *animation_indices = animations.for_state(&remote_player.facing, false);
+            continue;
+        }
+
+        // A new fix can cut a fade short
+        if sprite.color != Color::WHITE {
+            sprite.color = Color::WHITE;
+        }
+
+        let extrapolating = elapsed < remote_player.extrapolation_time;
 
-            if progress >= 1.0 {
-                // Movement complete - snap to final position
-                transform.translation = remote_player.target_position;
-                remote_player.is_moving = false;
-
-                // Set to idle animation based on last direction
-                if movement_vector.x.abs() > movement_vector.y.abs() {
-                    if movement_vector.x > 0.0 {
-                        *animation_indices = animations.idle_east;
-                    } else {
-                        *animation_indices = animations.idle_west;
-                    }
-                } else {
-                    if movement_vector.y > 0.0 {
-                        *animation_indices = animations.idle_north;
-                    } else {
-                        *animation_indices = animations.idle_south;
-                    }
-                }
+        // Calculate movement direction for animation
+        let (position, movement_vector) = if !remote_player.path.is_empty() {
+            // Walk the path at a steady pace
+            let length: f32 = remote_player
+                .path
+                .windows(2)
+                .map(|w| w[0].distance(w[1]))
+                .sum();
+            point_along(&remote_player.path, length * progress)
+        } else {
+            // Dead-reckoned position, frozen once the extrapolation time is used up
+            let extrapolated = remote_player.target_position
+                + (remote_player.velocity * elapsed.min(remote_player.extrapolation_time))
+                    .extend(0.0);
+            // Fade out the offset between where the avatar was and the fix
+            // Using smooth easing (ease-out)
+            let smooth_progress = 1.0 - (1.0 - progress).powi(3);
+            let offset = remote_player.start_position - remote_player.target_position;
+            let movement_vector = if extrapolating {
+                remote_player.velocity.extend(0.0)
             } else {
-                // Interpolate between start and target positions
-                // Using smooth easing (ease-out)
-                let smooth_progress = 1.0 - (1.0 - progress).powi(3);
+                remote_player.target_position - remote_player.start_position
+            };
+            (extrapolated + offset * (1.0 - smooth_progress), movement_vector)
+        };
 
-                transform.translation = remote_player
-                    .start_position
-                    .lerp(remote_player.target_position, smooth_progress);
+        // Update animation based on primary movement direction
+        if movement_vector.x.abs() > movement_vector.y.abs() {
+            // Horizontal movement is dominant
+            if movement_vector.x > 0.0 {
+                // Moving right (east)
+                *animation_indices = animations.walk_east;
+            } else {
+                // Moving left (west)
+                *animation_indices = animations.walk_west;
+            }
+        } else {
+            // Vertical movement is dominant
+            if movement_vector.y > 0.0 {
+                // Moving up (north)
+                *animation_indices = animations.walk_north;
+            } else {
+                // Moving down (south)
+                *animation_indices = animations.walk_south;
+            }
+        }
+
+        transform.translation = position;
+
+        let path_done = !remote_player.path.is_empty() && progress >= 1.0;
+        if path_done || (progress >= 1.0 && !extrapolating) {
+            // Movement complete
+            remote_player.is_moving = false;
+            remote_player.path.clear();
+
+            if remote_player.velocity != Vec2::ZERO {
+                // We stopped guessing, so stop walking
+                *animation_indices = animations.for_state(&remote_player.facing, false);
+            } else {
+                // Settle in the facing and walking/idle state the station reported
+                *animation_indices =
+                    animations.for_state(&remote_player.facing, remote_player.reported_moving);
             }
         }
     }
@@ -0,0 +1,325 @@
+// Per-callsign save game: the map the player was last on and where they stood on
+// each map, inventory, stats, stations heard, and chat history including direct
+// messages.
+//
+// Saves are JSON in saves/<callsign>.json with a schema version. They are written
+// to a temporary file first and then renamed into place, keeping the previous
+// save as a .bak, so a crash mid-write never leaves a half-written save behind.
+// If the save can't be read, the backup is tried, and if that fails too the bad
+// file is kept aside as .broken and the player starts fresh.
+
+use crate::components::{Entity, RemotePlayer};
+use crate::connection::tnc_integration::GameState;
+use crate::systems::world_map::WorldMap;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const SAVE_DIR: &str = "saves";
+// Bump when the layout of SaveData changes, and add a step to `migrate`
+const SAVE_VERSION: u32 = 2;
+// How often the game is saved while playing
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(60);
+// Chat lines kept in the save
+const SAVED_CHAT_LINES: usize = 100;
+// Movement faster than this in one frame is a teleport, not walking
+const MAX_STEP: f32 = 64.0;
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct SaveData {
+    pub version: u32,
+    pub callsign: String,
+    // Map the player was last on
+    pub map: Option<String>,
+    // Last position on each map the player has been to
+    pub positions: HashMap<String, [f32; 2]>,
+    pub inventory: Vec<InventoryItem>,
+    pub stats: Stats,
+    // Every station heard, by callsign
+    pub contacts: BTreeMap<String, Contact>,
+    pub chat_history: Vec<String>,
+    // Direct message conversations by the other station's callsign
+    pub direct_messages: BTreeMap<String, Vec<String>>,
+}
+
+// Nothing is picked up yet; the inventory keeps its place in the schema until
+// items exist
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct InventoryItem {
+    pub name: String,
+    pub count: u32,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Stats {
+    pub sessions: u32,
+    pub play_time_secs: f64,
+    pub distance_walked: f64,
+}
+
+// When a station was first and last heard (Unix seconds)
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Contact {
+    pub first_heard: u64,
+    pub last_heard: u64,
+}
+
+#[derive(Resource)]
+pub struct SaveGame {
+    path: PathBuf,
+    pub data: SaveData,
+}
+
+impl SaveGame {
+    // Load the callsign's save, falling back to the backup and then to a fresh
+    // save. Never fails; problems are reported and the player starts over.
+    pub fn load(callsign: &str) -> Self {
+        let path = save_path(callsign);
+        let backup = path.with_extension("json.bak");
+
+        let data = match read_save(&path) {
+            Ok(Some(data)) => Some(data),
+            Ok(None) => read_save(&backup).ok().flatten(),
+            Err(e) => {
+                println!("[!] {}", e);
+                // Keep the unreadable file for a closer look instead of letting
+                // the next save push it over the backup
+                let broken = path.with_extension("json.broken");
+                if fs::rename(&path, &broken).is_ok() {
+                    println!("[!] Moved unreadable save to {}", broken.display());
+                }
+                match read_save(&backup) {
+                    Ok(Some(data)) => {
+                        println!("[i] Restored save from {}", backup.display());
+                        Some(data)
+                    }
+                    _ => None,
+                }
+            }
+        };
+
+        let data = data.unwrap_or_else(|| {
+            println!("[i] Starting a new save for {}", callsign);
+            SaveData {
+                version: SAVE_VERSION,
+                callsign: callsign.to_string(),
+                ..default()
+            }
+        });
+
+        let mut save_game = Self { path, data };
+        save_game.data.stats.sessions += 1;
+        save_game
+    }
+
+    pub fn position(&self, map: &str) -> Option<Vec2> {
+        self.data.positions.get(map).map(|&[x, y]| Vec2::new(x, y))
+    }
+
+    // Note where the player is on a map, which becomes the map they were last on
+    pub fn remember(&mut self, map: &str, position: Vec2) {
+        self.data
+            .positions
+            .insert(map.to_string(), [position.x, position.y]);
+        self.data.map = Some(map.to_string());
+    }
+
+    // Write the save to a temporary file, then swap it in, keeping the old one
+    // as the backup
+    pub fn save(&self) -> Result<(), String> {
+        if self.data.callsign.is_empty() {
+            return Ok(());
+        }
+        let json = serde_json::to_string_pretty(&self.data)
+            .map_err(|e| format!("Failed to encode save: {}", e))?;
+
+        fs::create_dir_all(SAVE_DIR).map_err(|e| format!("Failed to create {}: {}", SAVE_DIR, e))?;
+        let temp = self.path.with_extension("json.tmp");
+        let backup = self.path.with_extension("json.bak");
+        let write = || -> std::io::Result<()> {
+            let mut file = fs::File::create(&temp)?;
+            file.write_all(json.as_bytes())?;
+            file.sync_all()?;
+            if self.path.exists() {
+                // Renaming over an existing file fails on Windows
+                let _ = fs::remove_file(&backup);
+                fs::rename(&self.path, &backup)?;
+            }
+            fs::rename(&temp, &self.path)
+        };
+        write().map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))
+    }
+
+    // Copy what is only kept in the world into the save: where the player is, and
+    // the chat history and conversations
+    fn capture(&mut self, world_map: &WorldMap, player: Option<&Transform>, game_state: &GameState) {
+        if let Some(transform) = player {
+            self.remember(&world_map.path, transform.translation.truncate());
+        }
+        let skip = game_state.chat_messages.len().saturating_sub(SAVED_CHAT_LINES);
+        self.data.chat_history = game_state.chat_messages[skip..].to_vec();
+        self.data.direct_messages = game_state
+            .direct_messages
+            .iter()
+            .map(|(callsign, lines)| {
+                let skip = lines.len().saturating_sub(SAVED_CHAT_LINES);
+                (callsign.clone(), lines[skip..].to_vec())
+            })
+            .collect();
+    }
+}
+
+// One file per callsign, with anything that isn't safe in a file name replaced
+fn save_path(callsign: &str) -> PathBuf {
+    let name: String = callsign
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    Path::new(SAVE_DIR).join(format!("{}.json", name))
+}
+
+// Ok(None) if there is no save at `path`
+fn read_save(path: &Path) -> Result<Option<SaveData>, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let value: serde_json::Value = serde_json::from_str(&text)
+        .map_err(|e| format!("Save {} is corrupt: {}", path.display(), e))?;
+    migrate(value)
+        .map(Some)
+        .map_err(|e| format!("Save {} can't be loaded: {}", path.display(), e))
+}
+
+// Bring a save written by an older version up to the current schema, one
+// version at a time
+fn migrate(mut value: serde_json::Value) -> Result<SaveData, String> {
+    if !value.is_object() {
+        return Err("not a save file".to_string());
+    }
+    let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+    if version > SAVE_VERSION {
+        return Err(format!("written by a newer version (schema {})", version));
+    }
+    // Schema 2 added direct messages and brought the inventory back. Keep an
+    // inventory written by the first schema 1 saves.
+    if version < 2 {
+        if let Some(save) = value.as_object_mut() {
+            save.entry("inventory").or_insert_with(|| serde_json::json!([]));
+            save.entry("direct_messages").or_insert_with(|| serde_json::json!({}));
+        }
+    }
+    value["version"] = SAVE_VERSION.into();
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+// Count play time and distance walked, and note every station heard
+pub fn track_progress(
+    time: Res<Time>,
+    player: Query<&Transform, (With<Entity>, Without<RemotePlayer>)>,
+    game_state: Res<GameState>,
+    mut save_game: ResMut<SaveGame>,
+    mut last_position: Local<Option<Vec2>>,
+) {
+    let stats = &mut save_game.data.stats;
+    stats.play_time_secs += time.delta_secs_f64();
+    if let Ok(transform) = player.single() {
+        let position = transform.translation.truncate();
+        if let Some(last) = *last_position {
+            let step = last.distance(position);
+            if step < MAX_STEP {
+                stats.distance_walked += step as f64;
+            }
+        }
+        *last_position = Some(position);
+    }
+
+    if !game_state.is_changed() {
+        return;
+    }
+    let (now, wall_clock) = (Instant::now(), SystemTime::now());
+    for (callsign, &heard) in &game_state.last_heard {
+        let heard = unix_time(
+            wall_clock
+                .checked_sub(now.duration_since(heard))
+                .unwrap_or(wall_clock),
+        );
+        let contact = save_game
+            .data
+            .contacts
+            .entry(callsign.clone())
+            .or_insert(Contact {
+                first_heard: heard,
+                last_heard: heard,
+            });
+        contact.last_heard = contact.last_heard.max(heard);
+    }
+}
+
+// Save every AUTOSAVE_INTERVAL while playing
+pub fn autosave(
+    mut save_game: ResMut<SaveGame>,
+    player: Query<&Transform, (With<Entity>, Without<RemotePlayer>)>,
+    world_map: Res<WorldMap>,
+    game_state: Res<GameState>,
+    mut last_save: Local<Option<Instant>>,
+) {
+    let now = Instant::now();
+    let Some(last) = *last_save else {
+        *last_save = Some(now);
+        return;
+    };
+    if now.duration_since(last) < AUTOSAVE_INTERVAL {
+        return;
+    }
+    *last_save = Some(now);
+
+    save_game.capture(&world_map, player.single().ok(), &game_state);
+    if let Err(e) = save_game.save() {
+        println!("[!] {}", e);
+    }
+}
+
+// Save when the game closes. Runs in Last, after the frame's AppExit was written.
+pub fn save_game_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    mut save_game: ResMut<SaveGame>,
+    player: Query<&Transform, (With<Entity>, Without<RemotePlayer>)>,
+    world_map: Res<WorldMap>,
+    game_state: Res<GameState>,
+) {
+    if exit_events.read().next().is_none() {
+        return;
+    }
+    save_game.capture(&world_map, player.single().ok(), &game_state);
+    match save_game.save() {
+        Ok(()) => println!("[i] Saved game for {}", save_game.data.callsign),
+        Err(e) => println!("[!] {}", e),
+    }
+}
+
+// Save when going back to the menu, while the player is still in the world
+pub fn save_game_on_quit(
+    mut save_game: ResMut<SaveGame>,
+    player: Query<&Transform, (With<Entity>, Without<RemotePlayer>)>,
+    world_map: Res<WorldMap>,
+    game_state: Res<GameState>,
+) {
+    save_game.capture(&world_map, player.single().ok(), &game_state);
+    match save_game.save() {
+        Ok(()) => println!("[i] Saved game for {}", save_game.data.callsign),
+        Err(e) => println!("[!] {}", e),
+    }
+}
@@ -0,0 +1,264 @@
+use crate::config::{BINDABLE_KEYS, GameConfig, Settings, key_name};
+use crate::connection::resources::PositionUpdateTime;
+use crate::menu::{AppState, MenuConfig};
+use crate::systems::gui::ChatInputState;
+use bevy::audio::{AudioSink, AudioSinkPlayback, GlobalVolume, Volume};
+use bevy::prelude::*;
+use bevy::window::{MonitorSelection, PresentMode, PrimaryWindow, WindowMode};
+use bevy_egui::{EguiContexts, egui};
+
+// Position update interval choices in the settings window (seconds)
+const INTERVAL_RANGE: std::ops::RangeInclusive<u64> = 5..=600;
+
+// Actions that can be bound to a key
+#[derive(Clone, Copy, PartialEq)]
+pub enum KeyAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Chat,
+}
+
+impl KeyAction {
+    const ALL: [KeyAction; 5] = [
+        KeyAction::Up,
+        KeyAction::Down,
+        KeyAction::Left,
+        KeyAction::Right,
+        KeyAction::Chat,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            KeyAction::Up => "Walk north",
+            KeyAction::Down => "Walk south",
+            KeyAction::Left => "Walk west",
+            KeyAction::Right => "Walk east",
+            KeyAction::Chat => "Chat",
+        }
+    }
+
+    fn key(self, settings: &mut Settings) -> &mut KeyCode {
+        let keys = &mut settings.keys;
+        match self {
+            KeyAction::Up => &mut keys.up,
+            KeyAction::Down => &mut keys.down,
+            KeyAction::Left => &mut keys.left,
+            KeyAction::Right => &mut keys.right,
+            KeyAction::Chat => &mut keys.chat,
+        }
+    }
+}
+
+// Whether the settings window is up, and which action is waiting for a key
+#[derive(Resource, Default)]
+pub struct SettingsWindowState {
+    pub open: bool,
+    rebinding: Option<KeyAction>,
+}
+
+// Esc opens and closes the settings window. Changes apply as they are made and
+// are written to game_config.ini when the window closes.
+#[allow(clippy::too_many_arguments)]
+pub fn settings_window(
+    mut contexts: EguiContexts,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut window_state: ResMut<SettingsWindowState>,
+    mut settings: ResMut<Settings>,
+    mut pos_update_time: ResMut<PositionUpdateTime>,
+    mut game_config: ResMut<GameConfig>,
+    mut menu_config: ResMut<MenuConfig>,
+    chat_state: Res<ChatInputState>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    // Waiting for the key to bind; Esc cancels
+    if let Some(action) = window_state.rebinding {
+        if keyboard.just_pressed(KeyCode::Escape) {
+            window_state.rebinding = None;
+        } else if let Some(&key) = keyboard
+            .get_just_pressed()
+            .find(|key| BINDABLE_KEYS.contains(key))
+        {
+            // A key does one thing: an action already on it takes this one's old key
+            let old = std::mem::replace(action.key(&mut settings), key);
+            for other in KeyAction::ALL {
+                if other != action && *other.key(&mut settings) == key {
+                    *other.key(&mut settings) = old;
+                }
+            }
+            window_state.rebinding = None;
+        }
+    } else if keyboard.just_pressed(KeyCode::Escape) && !chat_state.active {
+        window_state.open = !window_state.open;
+        if !window_state.open {
+            save_settings(&settings, &pos_update_time, &mut game_config, &mut menu_config);
+        }
+    }
+
+    if !window_state.open {
+        return;
+    }
+
+    let ctx = contexts.ctx_mut();
+
+    // Edit a copy so the resource only changes when a value does
+    let mut edited = settings.clone();
+    let mut interval = pos_update_time.0;
+    let mut close = false;
+
+    egui::Window::new("Settings")
+        .frame(egui::Frame {
+            fill: egui::Color32::from_rgba_unmultiplied(20, 20, 20, 220),
+            stroke: egui::Stroke::new(
+                1.0,
+                egui::Color32::from_rgba_unmultiplied(100, 100, 100, 200),
+            ),
+            inner_margin: egui::Margin::same(6),
+            ..Default::default()
+        })
+        .resizable(false)
+        .collapsible(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            egui::Grid::new("settings_grid")
+                .num_columns(2)
+                .spacing([20.0, 8.0])
+                .show(ui, |ui| {
+                    ui.label("Position updates (s)");
+                    ui.add(egui::Slider::new(&mut interval, INTERVAL_RANGE).logarithmic(true));
+                    ui.end_row();
+
+                    ui.label("Slotted position updates");
+                    ui.checkbox(&mut edited.slotted_beacons, "");
+                    ui.end_row();
+
+                    ui.label("Ask for chat ACKs");
+                    ui.checkbox(&mut edited.ack_chat, "");
+                    ui.end_row();
+
+                    ui.label("Music volume");
+                    ui.add(egui::Slider::new(&mut edited.volume, Settings::VOLUME_RANGE));
+                    ui.end_row();
+
+                    ui.label("Zoom");
+                    ui.add(egui::Slider::new(&mut edited.zoom, Settings::ZOOM_RANGE).step_by(0.5));
+                    ui.end_row();
+
+                    ui.label("Fullscreen");
+                    ui.checkbox(&mut edited.fullscreen, "");
+                    ui.end_row();
+
+                    ui.label("VSync");
+                    ui.checkbox(&mut edited.vsync, "");
+                    ui.end_row();
+
+                    for action in KeyAction::ALL {
+                        ui.label(action.label());
+                        let text = if window_state.rebinding == Some(action) {
+                            "Press a key...".to_string()
+                        } else {
+                            key_name(*action.key(&mut edited))
+                        };
+                        if ui.button(text).clicked() {
+                            window_state.rebinding = Some(action);
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if ui.button("Reset keys").clicked() {
+                    edited.keys = Default::default();
+                }
+                if ui.button("Close").clicked() {
+                    close = true;
+                }
+                // Leave the frequency and go back to pick another station or link
+                if ui.button("Quit to menu").clicked() {
+                    close = true;
+                    next_state.set(AppState::Menu);
+                }
+            });
+        });
+
+    if edited != *settings {
+        *settings = edited;
+    }
+    if interval != pos_update_time.0 {
+        pos_update_time.0 = interval;
+    }
+    if close {
+        window_state.open = false;
+        window_state.rebinding = None;
+        save_settings(&settings, &pos_update_time, &mut game_config, &mut menu_config);
+    }
+}
+
+// Keep the settings, and the interval in the station profile in use
+fn save_settings(
+    settings: &Settings,
+    pos_update_time: &PositionUpdateTime,
+    game_config: &mut GameConfig,
+    menu_config: &mut MenuConfig,
+) {
+    if let Err(e) = settings.save() {
+        println!("[!] {}", e);
+    }
+
+    menu_config.position_update_time = pos_update_time.0.to_string();
+    let mut station = game_config.active_station().clone();
+    if station.position_update_time != menu_config.position_update_time {
+        station.position_update_time = menu_config.position_update_time.clone();
+        let name = game_config.active.clone();
+        game_config.set(&name, station);
+        if let Err(e) = game_config.save() {
+            println!("[!] {}", e);
+        }
+    }
+    println!("[i] Settings saved");
+}
+
+// Push changed settings to the audio, window and camera
+pub fn apply_settings(
+    settings: Res<Settings>,
+    mut volume: ResMut<GlobalVolume>,
+    mut music: Query<&mut AudioSink>,
+    mut window: Single<&mut Window, With<PrimaryWindow>>,
+    mut projection: Query<&mut Projection, With<Camera2d>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    // The global volume only applies to sounds started after it changes, so
+    // turn the music that is already playing up or down too
+    volume.volume = Volume::Linear(settings.volume);
+    for mut sink in music.iter_mut() {
+        sink.set_volume(Volume::Linear(settings.volume));
+    }
+
+    let mode = if settings.fullscreen {
+        WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+    } else {
+        WindowMode::Windowed
+    };
+    if window.mode != mode {
+        window.mode = mode;
+    }
+    let present_mode = if settings.vsync {
+        PresentMode::AutoVsync
+    } else {
+        PresentMode::AutoNoVsync
+    };
+    if window.present_mode != present_mode {
+        window.present_mode = present_mode;
+    }
+
+    for mut projection in projection.iter_mut() {
+        if let Projection::Orthographic(orthographic) = &mut *projection {
+            orthographic.scale = 1.0 / settings.zoom;
+        }
+    }
+}
@@ -1,10 +1,13 @@
+use crate::config::Settings;
 use crate::connection::tnc_integration::GameState;
+use crate::components::RemotePlayer;
+use crate::systems::world_map::{LayerDepth, MapGeometry, WorldMap};
 use bevy::prelude::*;
 use bevy_ecs_tiled::prelude::*;
 use iyes_perf_ui::prelude::*;
 
-// Initialize game scene: camera, performance UI, and tilemap
-pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+// Initialize game scene: camera and performance UI (the tilemap comes with the WorldMap)
+pub fn setup(mut commands: Commands, settings: Res<Settings>) {
     commands.spawn((
         Camera2d,
         Camera {
@@ -13,7 +16,7 @@ pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         },
         Projection::Orthographic(OrthographicProjection {
             near: -1000.0,
-            scale: 0.25,
+            scale: 1.0 / settings.zoom,
             ..OrthographicProjection::default_3d()
         }),
     ));
@@ -27,24 +30,60 @@ pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         PerfUiEntryFPSWorst::default(),
         PerfUiEntryFPS::default(),
     ));
-    commands.spawn((
-        TiledMapHandle(asset_server.load("map.tmx")),
-        TilemapAnchor::Center,
-    ));
 }
 
+// Clear the game world when going back to the menu: the map, camera, players
+// and music
+#[allow(clippy::type_complexity)]
+pub fn despawn_game_world(
+    mut commands: Commands,
+    query: Query<
+        Entity,
+        Or<(
+            With<MapGeometry>,
+            With<Camera2d>,
+            With<PerfUiRoot>,
+            With<crate::components::Entity>,
+            With<RemotePlayer>,
+            With<AudioPlayer>,
+        )>,
+    >,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
 
+// Place each tile layer at the depth the map gives it. Layers that are drawn as
+// y-sorted sprites instead are hidden. Tile layers are spawned under the map in
+// map order, so the n-th one found is the map's n-th tile layer.
 pub fn adjust_layer_z_ordering(
-    mut layer_query: Query<(&mut Transform, &TiledMapTileLayer, Entity), Changed<TiledMapTileLayer>>,
+    mut commands: Commands,
+    added: Query<(), Added<TiledMapTileLayer>>,
+    maps: Query<Entity, With<TiledMapHandle>>,
+    children: Query<&Children>,
+    mut layers: Query<&mut Transform, With<TiledMapTileLayer>>,
+    world_map: Res<WorldMap>,
 ) {
-    for (mut transform, _layer, entity) in layer_query.iter_mut() {
-        // Since TiledMapTileLayer does not have an 'id' field, use the entity's id as a fallback for ordering.
-        let layer_index = entity.index();
-        print!("Layer entity index: {}\n", layer_index);
-        if layer_index == 35 {
-            transform.translation.z = 0.0;  // First layer (layer0) at Z = 0
-        } else {
-            transform.translation.z = 3.0;  // Other layers at Z = 11.0, 12.0, etc.
+    if added.is_empty() {
+        return;
+    }
+    for map in maps.iter() {
+        let tile_layers: Vec<Entity> = children
+            .iter_descendants_depth_first(map)
+            .filter(|&entity| layers.contains(entity))
+            .collect();
+        for (index, entity) in tile_layers.into_iter().enumerate() {
+            let Ok(mut transform) = layers.get_mut(entity) else {
+                continue;
+            };
+            match world_map.layer_depth(index) {
+                Some(LayerDepth::Fixed(z)) => transform.translation.z = z,
+                Some(LayerDepth::YSorted) => {
+                    commands.entity(entity).insert(Visibility::Hidden);
+                }
+                None => println!("[!] No depth for map tile layer {}", index),
+            }
         }
     }
 }
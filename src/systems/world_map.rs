@@ -0,0 +1,743 @@
+// Walkability grid and static collision geometry for the loaded Tiled map, and
+// A* pathfinding over the grid
+//
+// Collision comes from two places: shapes drawn on individual tiles in the
+// tileset editor, and shapes on an object layer named "Collision". Both become
+// avian2d static colliders. A cell is blocked for pathfinding when it holds a
+// colliding tile or overlaps a collision object. Cell (0, 0) is the top-left
+// tile, and the map is drawn centered on the world origin (TilemapAnchor::Center).
+//
+// Tile layers are drawn at the depth in their "z" custom property, or stacked in
+// layer order below the players. Layers with a "y_sort" property set are drawn as
+// individual sprites instead, sorted by y together with the players so they can
+// walk both behind and in front of trees and tall props.
+//
+// Each map is a zone (see ZONES). An object layer named "Portals" holds the ways
+// between them: any object with a "map" property is a portal to that map, and
+// its optional "entry" property names the object in the target map's Portals
+// layer to arrive at. Objects without a "map" property are just arrival points.
+//
+// Objects on a layer named "Spawns" are where players start when they have no
+// remembered position on the map.
+
+use crate::systems::save::SaveGame;
+use avian2d::prelude::*;
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::*;
+use std::cmp::Reverse;
+use rand::{Rng, rng};
+use std::collections::{BinaryHeap, HashMap};
+use std::path::PathBuf;
+use tiled::{ObjectData, ObjectShape, PropertyValue};
+
+pub const MAP_PATH: &str = "map.tmx";
+
+// Every map a player can be on. The id goes out in position packets (4 bits).
+pub struct Zone {
+    pub id: u8,
+    pub map: &'static str,
+    pub name: &'static str,
+}
+
+pub const ZONES: &[Zone] = &[
+    Zone {
+        id: 0,
+        map: MAP_PATH,
+        name: "Village",
+    },
+    Zone {
+        id: 1,
+        map: "hilltop.tmx",
+        name: "Hilltop",
+    },
+];
+
+pub fn zone_name(id: u8) -> String {
+    ZONES
+        .iter()
+        .find(|zone| zone.id == id)
+        .map_or_else(|| format!("zone {}", id), |zone| zone.name.to_string())
+}
+
+// Object layer whose shapes are walls
+const COLLISION_LAYER: &str = "Collision";
+// Object layer holding portals and arrival points
+const PORTAL_LAYER: &str = "Portals";
+// Object layer holding spawn points
+const SPAWN_LAYER: &str = "Spawns";
+
+// Depth between tile layers that have no "z" property
+const LAYER_Z_STEP: f32 = 0.1;
+
+// Y-sorted sprites (players and tall props) are drawn between these depths,
+// above every layer stacked by order
+const Y_SORT_Z: f32 = 2.0;
+const Y_SORT_DEPTH: f32 = 0.9;
+
+// Path costs in tenths of a cell
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+#[derive(Resource)]
+pub struct WorldMap {
+    // Map file, relative to the assets folder
+    pub path: String,
+    pub zone: u8,
+    pub width: u32,
+    pub height: u32,
+    pub tile_size: Vec2,
+    blocked: Vec<bool>,
+    // Static collision geometry in world space
+    colliders: Vec<(Transform, Collider)>,
+    // Depth of each tile layer, in map order
+    layer_depths: Vec<LayerDepth>,
+    // Tiles of y-sorted layers, drawn as sprites
+    props: Vec<PropTile>,
+    portals: Vec<Portal>,
+    // Named objects of the Portals layer, in world space
+    entries: HashMap<String, Vec2>,
+    // Spawn points in world space
+    spawns: Vec<Vec2>,
+}
+
+// World-space area that takes the player to another map
+pub struct Portal {
+    min: Vec2,
+    max: Vec2,
+    pub map: String,
+    pub entry: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+pub enum LayerDepth {
+    Fixed(f32),
+    YSorted,
+}
+
+struct PropTile {
+    // Tileset image, relative to the assets folder
+    image: String,
+    tile_size: UVec2,
+    columns: u32,
+    rows: u32,
+    index: u32,
+    position: Vec2,
+    // World y the tile sorts at: the bottom of the prop it belongs to
+    sort_y: f32,
+    flip_x: bool,
+    flip_y: bool,
+}
+
+// Marks entities spawned from the map's data (tilemap, colliders, prop sprites)
+#[derive(Component)]
+pub struct MapGeometry;
+
+// Sprites whose depth follows their y position
+#[derive(Component)]
+pub struct YSort {
+    // Offset from the sprite's center to the point it sorts by (its feet)
+    pub foot_offset: f32,
+}
+
+// The folder the AssetServer loads from, so maps are found wherever the game
+// is started from
+fn assets_dir() -> PathBuf {
+    FileAssetReader::get_base_path().join("assets")
+}
+
+impl WorldMap {
+    // Read a map from the assets folder and collect its collision geometry
+    pub fn load(path: &str) -> Result<Self, String> {
+        let map = tiled::Loader::new()
+            .load_tmx_map(assets_dir().join(path))
+            .map_err(|e| format!("Failed to load {}: {}", path, e))?;
+
+        let mut world_map = Self::open(
+            map.width,
+            map.height,
+            Vec2::new(map.tile_width as f32, map.tile_height as f32),
+        );
+        world_map.path = path.to_string();
+        world_map.zone = ZONES
+            .iter()
+            .find(|zone| zone.map == path)
+            .map_or(0, |zone| zone.id);
+        let mut tile_layer_index = 0;
+        for layer in map.layers() {
+            if let Some(tile_layer) = layer.as_tile_layer() {
+                let depth = match (layer.properties.get("z"), layer.properties.get("y_sort")) {
+                    (Some(PropertyValue::FloatValue(z)), _) => LayerDepth::Fixed(*z),
+                    (Some(PropertyValue::IntValue(z)), _) => LayerDepth::Fixed(*z as f32),
+                    (_, Some(PropertyValue::BoolValue(true))) => LayerDepth::YSorted,
+                    _ => LayerDepth::Fixed(tile_layer_index as f32 * LAYER_Z_STEP),
+                };
+                world_map.layer_depths.push(depth);
+                tile_layer_index += 1;
+
+                for y in 0..map.height {
+                    for x in 0..map.width {
+                        let Some(layer_tile) = tile_layer.get_tile(x as i32, y as i32) else {
+                            continue;
+                        };
+                        if let LayerDepth::YSorted = depth {
+                            // A prop is a vertical run of tiles; all of it sorts at its base
+                            let mut base = y;
+                            while base + 1 < map.height
+                                && tile_layer.get_tile(x as i32, base as i32 + 1).is_some()
+                            {
+                                base += 1;
+                            }
+                            world_map.add_prop_tile(&layer_tile, UVec2::new(x, y), base);
+                        }
+                        let Some(tile) = layer_tile.get_tile() else {
+                            continue;
+                        };
+                        let Some(collision) = &tile.collision else {
+                            continue;
+                        };
+                        // Tile shapes are relative to the tile's top-left corner
+                        let corner = Vec2::new(x as f32, y as f32) * world_map.tile_size;
+                        for object in collision.object_data() {
+                            world_map.add_collider(object, corner);
+                        }
+                        world_map.blocked[(y * map.width + x) as usize] = true;
+                    }
+                }
+            } else if let Some(object_layer) = layer.as_object_layer() {
+                if layer.name.eq_ignore_ascii_case(COLLISION_LAYER) {
+                    for object in object_layer.objects() {
+                        if let Some((min, max)) = world_map.add_collider(&object, Vec2::ZERO) {
+                            world_map.block_area(min, max);
+                        }
+                    }
+                } else if layer.name.eq_ignore_ascii_case(PORTAL_LAYER) {
+                    for object in object_layer.objects() {
+                        world_map.add_portal(&object);
+                    }
+                } else if layer.name.eq_ignore_ascii_case(SPAWN_LAYER) {
+                    for object in object_layer.objects() {
+                        world_map.add_spawn(&object);
+                    }
+                }
+            }
+        }
+
+        Ok(world_map)
+    }
+
+    fn add_portal(&mut self, object: &ObjectData) {
+        let size = match object.shape {
+            ObjectShape::Rect { width, height } => Vec2::new(width, height),
+            _ => Vec2::ZERO,
+        };
+        // Map pixels have y pointing down, so the top-left corner is the world max y
+        let top_left = self.map_to_world(Vec2::new(object.x, object.y));
+        let min = Vec2::new(top_left.x, top_left.y - size.y);
+        let max = Vec2::new(top_left.x + size.x, top_left.y);
+        if !object.name.is_empty() {
+            self.entries.insert(object.name.clone(), (min + max) / 2.0);
+        }
+
+        let property = |name: &str| match object.properties.get(name) {
+            Some(PropertyValue::StringValue(value)) | Some(PropertyValue::FileValue(value))
+                if !value.is_empty() =>
+            {
+                Some(value.clone())
+            }
+            _ => None,
+        };
+        if let Some(map) = property("map") {
+            self.portals.push(Portal {
+                min,
+                max,
+                map,
+                entry: property("entry"),
+            });
+        }
+    }
+
+    // The portal the given world position stands in, if any
+    pub fn portal_at(&self, position: Vec2) -> Option<&Portal> {
+        self.portals.iter().find(|portal| {
+            position.cmpge(portal.min).all() && position.cmple(portal.max).all()
+        })
+    }
+
+    // Where a named arrival point is, in world space
+    pub fn entry(&self, name: &str) -> Option<Vec2> {
+        self.entries.get(name).copied()
+    }
+
+    // Points spawn at their position, areas at their center
+    fn add_spawn(&mut self, object: &ObjectData) {
+        let center = match object.shape {
+            ObjectShape::Rect { width, height } | ObjectShape::Ellipse { width, height } => {
+                Vec2::new(object.x + width / 2.0, object.y + height / 2.0)
+            }
+            _ => Vec2::new(object.x, object.y),
+        };
+        self.spawns.push(self.map_to_world(center));
+    }
+
+    // Where a player starts on this map: the same spawn point every time for a
+    // given callsign, a random one without a callsign, or the middle of the map
+    // if it has none
+    pub fn spawn_point(&self, callsign: &str) -> Vec2 {
+        if self.spawns.is_empty() {
+            return Vec2::ZERO;
+        }
+        let index = if callsign.is_empty() {
+            rng().random_range(0..self.spawns.len())
+        } else {
+            // FNV-1a, so the choice doesn't change between builds
+            let hash = callsign.bytes().fold(0x811c9dc5u32, |hash, byte| {
+                (hash ^ byte as u32).wrapping_mul(0x01000193)
+            });
+            hash as usize % self.spawns.len()
+        };
+        self.spawns[index]
+    }
+
+    // Keep a world position at least `margin` inside the map's edges. On an axis
+    // where the map is smaller than twice the margin, it is centered instead.
+    pub fn clamp(&self, position: Vec2, margin: Vec2) -> Vec2 {
+        let limit = (self.size() / 2.0 - margin).max(Vec2::ZERO);
+        position.clamp(-limit, limit)
+    }
+
+    fn add_prop_tile(&mut self, layer_tile: &tiled::LayerTile, cell: UVec2, base_row: u32) {
+        let tileset = layer_tile.get_tileset();
+        let Some(image) = &tileset.image else {
+            return;
+        };
+        // The loader resolves image paths against the assets folder we loaded from
+        let assets = assets_dir();
+        let image_path = image.source.strip_prefix(&assets).unwrap_or(&image.source);
+        let tile_size = UVec2::new(tileset.tile_width, tileset.tile_height);
+        let bottom_edge = self.cell_to_world(UVec2::new(cell.x, base_row)).y - self.tile_size.y / 2.0;
+        self.props.push(PropTile {
+            image: image_path.to_string_lossy().replace('\\', "/"),
+            tile_size,
+            columns: tileset.columns,
+            rows: tileset.tilecount.div_ceil(tileset.columns.max(1)),
+            index: layer_tile.id(),
+            position: self.cell_to_world(cell),
+            sort_y: bottom_edge,
+            flip_x: layer_tile.flip_h,
+            flip_y: layer_tile.flip_v,
+        });
+    }
+
+    // Depth of the map's `index`-th tile layer
+    pub fn layer_depth(&self, index: usize) -> Option<LayerDepth> {
+        self.layer_depths.get(index).copied()
+    }
+
+    // Spawn everything drawn or collided with from this map, tagged MapGeometry
+    // so a zone change can clear it
+    pub fn spawn(
+        &self,
+        commands: &mut Commands,
+        asset_server: &AssetServer,
+        texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+    ) {
+        commands.spawn((
+            MapGeometry,
+            TiledMapHandle(asset_server.load(&self.path)),
+            TilemapAnchor::Center,
+        ));
+
+        // Static collision geometry
+        for (transform, collider) in &self.colliders {
+            commands.spawn((MapGeometry, RigidBody::Static, collider.clone(), *transform));
+        }
+
+        // Tiles of y-sorted layers, at their sorted depth
+        let mut layouts: HashMap<&str, Handle<TextureAtlasLayout>> = HashMap::new();
+        for prop in &self.props {
+            let layout = layouts
+                .entry(&prop.image)
+                .or_insert_with(|| {
+                    texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
+                        prop.tile_size,
+                        prop.columns,
+                        prop.rows,
+                        None,
+                        None,
+                    ))
+                })
+                .clone();
+            let mut sprite = Sprite::from_atlas_image(
+                asset_server.load(&prop.image),
+                TextureAtlas {
+                    layout,
+                    index: prop.index as usize,
+                },
+            );
+            sprite.flip_x = prop.flip_x;
+            sprite.flip_y = prop.flip_y;
+            commands.spawn((
+                MapGeometry,
+                sprite,
+                Transform::from_translation(prop.position.extend(self.y_sort_z(prop.sort_y))),
+            ));
+        }
+    }
+
+    // Depth for a sprite whose feet are at world y `y`; lower on screen is nearer
+    pub fn y_sort_z(&self, y: f32) -> f32 {
+        let half_height = self.size().y / 2.0;
+        let t = ((half_height - y) / self.size().y.max(1.0)).clamp(0.0, 1.0);
+        Y_SORT_Z + t * Y_SORT_DEPTH
+    }
+
+    // Turn a Tiled shape at `origin` (map pixels, y down) into a world-space
+    // collider. Returns the shape's bounds in map pixels.
+    fn add_collider(&mut self, object: &ObjectData, origin: Vec2) -> Option<(Vec2, Vec2)> {
+        let position = origin + Vec2::new(object.x, object.y);
+        // Tiled rotates clockwise about the object's origin, with y pointing down
+        let rotation = Rot2::radians(-object.rotation.to_radians());
+        let (center, collider, points) = match &object.shape {
+            ObjectShape::Rect { width, height } => (
+                Vec2::new(width / 2.0, height / 2.0),
+                Collider::rectangle(*width, *height),
+                vec![Vec2::ZERO, Vec2::new(*width, *height)],
+            ),
+            ObjectShape::Ellipse { width, height } => (
+                Vec2::new(width / 2.0, height / 2.0),
+                Collider::ellipse(width / 2.0, height / 2.0),
+                vec![Vec2::ZERO, Vec2::new(*width, *height)],
+            ),
+            ObjectShape::Polygon { points } | ObjectShape::Polyline { points } => {
+                let points: Vec<Vec2> = points.iter().map(|&(x, y)| Vec2::new(x, y)).collect();
+                let flipped: Vec<Vec2> = points.iter().map(|p| Vec2::new(p.x, -p.y)).collect();
+                let collider = match &object.shape {
+                    ObjectShape::Polygon { .. } => Collider::convex_hull(flipped.clone())
+                        .unwrap_or_else(|| Collider::polyline(flipped, None)),
+                    _ => Collider::polyline(flipped, None),
+                };
+                (Vec2::ZERO, collider, points)
+            }
+            _ => return None,
+        };
+
+        // Shape center in world space, rotated about the object origin
+        let world_origin = self.map_to_world(position);
+        let offset = rotation * Vec2::new(center.x, -center.y);
+        let transform = Transform::from_translation((world_origin + offset).extend(0.0))
+            .with_rotation(Quat::from_rotation_z(rotation.as_radians()));
+        self.colliders.push((transform, collider));
+
+        // Bounds of the shape's corners, in map pixels
+        let corners: Vec<Vec2> = if points.len() == 2 {
+            vec![
+                points[0],
+                Vec2::new(points[1].x, 0.0),
+                points[1],
+                Vec2::new(0.0, points[1].y),
+            ]
+        } else {
+            points
+        };
+        let map_rotation = Rot2::radians(object.rotation.to_radians());
+        let (min, max) = corners.iter().fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(min, max), &corner| {
+                let p = position + map_rotation * corner;
+                (min.min(p), max.max(p))
+            },
+        );
+        Some((min, max))
+    }
+
+    // Block every cell overlapping the area (map pixels, y down)
+    fn block_area(&mut self, min: Vec2, max: Vec2) {
+        let first = (min / self.tile_size).floor().max(Vec2::ZERO);
+        let last = (max / self.tile_size).ceil().min(Vec2::new(self.width as f32, self.height as f32));
+        for y in first.y as u32..last.y as u32 {
+            for x in first.x as u32..last.x as u32 {
+                self.blocked[(y * self.width + x) as usize] = true;
+            }
+        }
+    }
+
+    // Map pixels (origin top-left, y down) to world space
+    fn map_to_world(&self, position: Vec2) -> Vec2 {
+        let half = self.size() / 2.0;
+        Vec2::new(position.x - half.x, half.y - position.y)
+    }
+
+    // A map with nothing in the way, used when the real one can't be read
+    pub fn open(width: u32, height: u32, tile_size: Vec2) -> Self {
+        Self {
+            path: MAP_PATH.to_string(),
+            zone: 0,
+            width,
+            height,
+            tile_size,
+            blocked: vec![false; (width * height) as usize],
+            colliders: Vec::new(),
+            layer_depths: Vec::new(),
+            props: Vec::new(),
+            portals: Vec::new(),
+            entries: HashMap::new(),
+            spawns: Vec::new(),
+        }
+    }
+
+    pub fn world_to_cell(&self, position: Vec2) -> Option<UVec2> {
+        let half = self.size() / 2.0;
+        let x = ((position.x + half.x) / self.tile_size.x).floor();
+        let y = ((half.y - position.y) / self.tile_size.y).floor();
+        if x < 0.0 || y < 0.0 || x >= self.width as f32 || y >= self.height as f32 {
+            return None;
+        }
+        Some(UVec2::new(x as u32, y as u32))
+    }
+
+    // World position of a cell's center
+    pub fn cell_to_world(&self, cell: UVec2) -> Vec2 {
+        let half = self.size() / 2.0;
+        Vec2::new(
+            (cell.x as f32 + 0.5) * self.tile_size.x - half.x,
+            half.y - (cell.y as f32 + 0.5) * self.tile_size.y,
+        )
+    }
+
+    // Map extent in world units
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.width as f32, self.height as f32) * self.tile_size
+    }
+
+    pub fn is_walkable(&self, cell: UVec2) -> bool {
+        cell.x < self.width
+            && cell.y < self.height
+            && !self.blocked[(cell.y * self.width + cell.x) as usize]
+    }
+
+    // True if a straight walk from `from` to `to` only crosses walkable cells
+    pub fn line_is_clear(&self, from: Vec2, to: Vec2) -> bool {
+        let step = self.tile_size.min_element() / 4.0;
+        let samples = (from.distance(to) / step).ceil().max(1.0) as u32;
+        (0..=samples).all(|i| {
+            let point = from.lerp(to, i as f32 / samples as f32);
+            self.world_to_cell(point).is_some_and(|cell| self.is_walkable(cell))
+        })
+    }
+
+    // How far one can walk from `from` along `direction` (at most `max`) before
+    // running into a blocked cell or the edge of the map
+    pub fn clear_distance(&self, from: Vec2, direction: Vec2, max: f32) -> f32 {
+        let direction = direction.normalize_or_zero();
+        let step = self.tile_size.min_element() / 4.0;
+        let mut distance = 0.0;
+        while distance < max {
+            let next = (distance + step).min(max);
+            let walkable = self
+                .world_to_cell(from + direction * next)
+                .is_some_and(|cell| self.is_walkable(cell));
+            if !walkable {
+                break;
+            }
+            distance = next;
+        }
+        distance
+    }
+
+    // Shortest walkable route from `from` to `to` as world-space waypoints,
+    // starting exactly at `from` and ending exactly at `to`, so there are always at
+    // least two. The start and goal cells are allowed even if blocked, since that
+    // is where the player actually is.
+    pub fn find_path(&self, from: Vec2, to: Vec2) -> Option<Vec<Vec2>> {
+        let start = self.world_to_cell(from)?;
+        let goal = self.world_to_cell(to)?;
+        if start == goal {
+            return Some(vec![from, to]);
+        }
+        let passable = |cell: UVec2| cell == start || cell == goal || self.is_walkable(cell);
+
+        let heuristic = |cell: UVec2| {
+            let dx = cell.x.abs_diff(goal.x);
+            let dy = cell.y.abs_diff(goal.y);
+            STRAIGHT_COST * dx.max(dy) + (DIAGONAL_COST - STRAIGHT_COST) * dx.min(dy)
+        };
+
+        let mut open = BinaryHeap::new();
+        let mut came_from: HashMap<UVec2, UVec2> = HashMap::new();
+        let mut cost: HashMap<UVec2, u32> = HashMap::new();
+        open.push(Reverse((heuristic(start), start.x, start.y)));
+        cost.insert(start, 0);
+
+        while let Some(Reverse((_, x, y))) = open.pop() {
+            let cell = UVec2::new(x, y);
+            if cell == goal {
+                break;
+            }
+            let current_cost = cost[&cell];
+
+            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)] {
+                let Some(next) = offset(cell, dx, dy) else {
+                    continue;
+                };
+                if next.x >= self.width || next.y >= self.height || !passable(next) {
+                    continue;
+                }
+                let diagonal = dx != 0 && dy != 0;
+                // No cutting corners past a blocked cell
+                if diagonal
+                    && !(offset(cell, dx, 0).is_some_and(passable)
+                        && offset(cell, 0, dy).is_some_and(passable))
+                {
+                    continue;
+                }
+
+                let next_cost =
+                    current_cost + if diagonal { DIAGONAL_COST } else { STRAIGHT_COST };
+                if cost.get(&next).is_none_or(|&known| next_cost < known) {
+                    cost.insert(next, next_cost);
+                    came_from.insert(next, cell);
+                    open.push(Reverse((next_cost + heuristic(next), next.x, next.y)));
+                }
+            }
+        }
+
+        if !came_from.contains_key(&goal) {
+            return None;
+        }
+
+        // Walk back from the goal, then swap the end cells for the exact positions
+        let mut cells = vec![goal];
+        while let Some(&previous) = came_from.get(cells.last().unwrap()) {
+            cells.push(previous);
+        }
+        cells.reverse();
+        let mut waypoints: Vec<Vec2> = cells.iter().map(|&cell| self.cell_to_world(cell)).collect();
+        waypoints[0] = from;
+        *waypoints.last_mut().unwrap() = to;
+
+        // Drop corners that can be cut in a straight line
+        let mut smoothed = vec![from];
+        let mut i = 0;
+        while i + 1 < waypoints.len() {
+            let mut furthest = i + 1;
+            while furthest + 1 < waypoints.len()
+                && self.line_is_clear(waypoints[i], waypoints[furthest + 1])
+            {
+                furthest += 1;
+            }
+            smoothed.push(waypoints[furthest]);
+            i = furthest;
+        }
+        Some(smoothed)
+    }
+}
+
+fn offset(cell: UVec2, dx: i32, dy: i32) -> Option<UVec2> {
+    Some(UVec2::new(
+        cell.x.checked_add_signed(dx)?,
+        cell.y.checked_add_signed(dy)?,
+    ))
+}
+
+// Build the walkability grid when the game starts, for the map the player was
+// last on
+pub fn load_world_map(mut commands: Commands, save_game: Res<SaveGame>) {
+    let path = save_game.data.map.as_deref().unwrap_or(MAP_PATH);
+    let world_map = WorldMap::load(path)
+        .or_else(|e| {
+            if path == MAP_PATH {
+                return Err(e);
+            }
+            println!("[!] {} - starting on {} instead", e, MAP_PATH);
+            WorldMap::load(MAP_PATH)
+        })
+        .unwrap_or_else(|e| {
+            println!("[!] {} - remote players will move in straight lines", e);
+            WorldMap::open(32, 32, Vec2::splat(32.0))
+        });
+    println!(
+        "[i] Loaded {}x{} walkability grid and {} colliders from {}",
+        world_map.width,
+        world_map.height,
+        world_map.colliders.len(),
+        world_map.path
+    );
+    commands.insert_resource(world_map);
+}
+
+// Spawn the current map: its tilemap, colliders and y-sorted prop sprites
+pub fn spawn_world_map(
+    mut commands: Commands,
+    world_map: Res<WorldMap>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    world_map.spawn(&mut commands, &asset_server, &mut texture_atlas_layouts);
+}
+
+// Keep moving sprites at the depth of their feet
+pub fn y_sort_sprites(
+    world_map: Option<Res<WorldMap>>,
+    mut query: Query<(&mut Transform, &YSort)>,
+) {
+    let Some(world_map) = world_map else {
+        return;
+    };
+    for (mut transform, y_sort) in query.iter_mut() {
+        let z = world_map.y_sort_z(transform.translation.y + y_sort.foot_offset);
+        if transform.translation.z != z {
+            transform.translation.z = z;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 5x5 cells of 32 units, blocked where given
+    fn map_with_blocked(cells: &[(u32, u32)]) -> WorldMap {
+        let mut map = WorldMap::open(5, 5, Vec2::splat(32.0));
+        for &(x, y) in cells {
+            map.blocked[(y * map.width + x) as usize] = true;
+        }
+        map
+    }
+
+    #[test]
+    fn same_cell_path_keeps_both_ends() {
+        let map = map_with_blocked(&[]);
+        let (from, to) = (Vec2::new(-70.0, 70.0), Vec2::new(-60.0, 60.0));
+        assert_eq!(map.find_path(from, to), Some(vec![from, to]));
+    }
+
+    #[test]
+    fn open_ground_is_a_straight_line() {
+        let map = map_with_blocked(&[]);
+        let (from, to) = (map.cell_to_world(UVec2::new(0, 0)), map.cell_to_world(UVec2::new(4, 4)));
+        assert_eq!(map.find_path(from, to), Some(vec![from, to]));
+    }
+
+    #[test]
+    fn path_goes_around_a_wall() {
+        // A wall down the middle with a gap at the bottom
+        let map = map_with_blocked(&[(2, 0), (2, 1), (2, 2), (2, 3)]);
+        let (from, to) = (map.cell_to_world(UVec2::new(0, 0)), map.cell_to_world(UVec2::new(4, 0)));
+        assert!(!map.line_is_clear(from, to));
+
+        let path = map.find_path(from, to).unwrap();
+        assert_eq!((path[0], *path.last().unwrap()), (from, to));
+        assert!(path.windows(2).all(|leg| map.line_is_clear(leg[0], leg[1])));
+        // Every leg is clear, so getting across at all means through the gap
+        assert!(path.len() > 2);
+    }
+
+    #[test]
+    fn no_path_through_a_closed_wall_or_off_the_map() {
+        let map = map_with_blocked(&[(2, 0), (2, 1), (2, 2), (2, 3), (2, 4)]);
+        let (from, to) = (map.cell_to_world(UVec2::new(0, 0)), map.cell_to_world(UVec2::new(4, 0)));
+        assert_eq!(map.find_path(from, to), None);
+        assert_eq!(map.find_path(from, Vec2::new(1000.0, 0.0)), None);
+    }
+}
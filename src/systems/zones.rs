@@ -0,0 +1,159 @@
+use crate::components::{AnimationIndices, Animations, Entity, RemotePlayer};
+use crate::connection::compression::encode_position;
+use crate::connection::message::MessageType;
+use crate::connection::resources::ListenOnly;
+use crate::connection::tnc_integration::GameState;
+use crate::connection::tnc_plugin::TncOutgoingEvent;
+use crate::systems::save::SaveGame;
+use crate::systems::remote_player::spawn_player_remote;
+use crate::systems::world_map::{MapGeometry, WorldMap, zone_name};
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+// The local player's pieces a zone change moves and announces
+type LocalPlayer<'a> = (
+    &'a mut Transform,
+    &'a mut LinearVelocity,
+    &'a Animations,
+    &'a AnimationIndices,
+);
+
+// Request to swap the current map for another one
+#[derive(Event)]
+pub struct ZoneChange {
+    pub map: String,
+    // Named arrival point in the target map
+    pub entry: Option<String>,
+}
+
+// Send the local player through any portal they walk into. Portals only fire
+// once the player has stepped off the one they arrived on.
+pub fn use_portals(
+    player: Query<&Transform, (With<Entity>, Without<RemotePlayer>)>,
+    world_map: Res<WorldMap>,
+    mut zone_changes: EventWriter<ZoneChange>,
+    mut armed: Local<bool>,
+) {
+    let Ok(transform) = player.single() else {
+        return;
+    };
+
+    match world_map.portal_at(transform.translation.truncate()) {
+        Some(portal) if *armed => {
+            *armed = false;
+            zone_changes.write(ZoneChange {
+                map: portal.map.clone(),
+                entry: portal.entry.clone(),
+            });
+        }
+        Some(_) => {}
+        None => *armed = true,
+    }
+}
+
+// Load the target map in place of the current one, move the player to the
+// arrival point, and swap the remote players for the ones in the new zone.
+#[allow(clippy::too_many_arguments)]
+pub fn change_zone(
+    mut commands: Commands,
+    mut zone_changes: EventReader<ZoneChange>,
+    mut world_map: ResMut<WorldMap>,
+    map_entities: Query<bevy::prelude::Entity, With<MapGeometry>>,
+    mut player: Query<LocalPlayer, (With<Entity>, Without<RemotePlayer>)>,
+    mut camera: Query<&mut Transform, (With<Camera2d>, Without<Entity>)>,
+    mut game_state: ResMut<GameState>,
+    mut save_game: ResMut<SaveGame>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut event_writer: EventWriter<TncOutgoingEvent>,
+    listen_only: Res<ListenOnly>,
+) {
+    let Some(change) = zone_changes.read().last() else {
+        return;
+    };
+
+    let new_map = match WorldMap::load(&change.map) {
+        Ok(new_map) => new_map,
+        Err(e) => {
+            println!("[!] Can't change zone: {}", e);
+            return;
+        }
+    };
+
+    // Clear out the old map and everyone on it
+    for entity in map_entities.iter() {
+        commands.entity(entity).despawn();
+    }
+    for (_, entity) in game_state.player_entities.drain() {
+        commands.entity(entity).despawn();
+    }
+
+    new_map.spawn(&mut commands, &asset_server, &mut texture_atlas_layouts);
+
+    // Arrive at the named entry, or a spawn point
+    let arrival = change
+        .entry
+        .as_deref()
+        .and_then(|entry| new_map.entry(entry))
+        .unwrap_or_else(|| new_map.spawn_point(&game_state.player_callsign));
+    if let Ok((mut transform, mut velocity, animations, current_animation)) = player.single_mut() {
+        // Remember where we left the old map, and that we're on the new one
+        save_game.remember(&world_map.path, transform.translation.truncate());
+        save_game.remember(&new_map.path, arrival);
+        if let Err(e) = save_game.save() {
+            println!("[!] {}", e);
+        }
+
+        transform.translation.x = arrival.x;
+        transform.translation.y = arrival.y;
+        velocity.0 = Vec2::ZERO;
+
+        // Let the others know right away instead of at the next beacon
+        if !listen_only.0 {
+            event_writer.write(TncOutgoingEvent {
+                message: encode_position(
+                    &game_state.player_callsign,
+                    arrival.x,
+                    arrival.y,
+                    animations.facing(current_animation),
+                    false,
+                    (0.0, 0.0),
+                    new_map.zone,
+                    None,
+                ),
+                message_type: MessageType::Position,
+                destination: None,
+            });
+        }
+    }
+    if let Ok(mut camera) = camera.single_mut() {
+        camera.translation.x = arrival.x;
+        camera.translation.y = arrival.y;
+    }
+
+    // Show whoever was last reported on the new map
+    let GameState {
+        last_fixes,
+        player_entities,
+        chat_messages,
+        ..
+    } = &mut *game_state;
+    for fix in last_fixes.values().filter(|fix| fix.zone == new_map.zone) {
+        // The fix may be old, so don't send them walking off on it
+        let mut fix = fix.clone();
+        fix.vx = 0.0;
+        fix.vy = 0.0;
+        let entity = spawn_player_remote(
+            &mut commands,
+            &asset_server,
+            &mut texture_atlas_layouts,
+            &new_map,
+            &fix,
+        );
+        player_entities.insert(fix.callsign.clone(), entity);
+    }
+
+    println!("[i] Entered {} ({})", zone_name(new_map.zone), new_map.path);
+    chat_messages.push(format!("* Entered {}", zone_name(new_map.zone)));
+    *world_map = new_map;
+}